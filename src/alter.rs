@@ -0,0 +1,156 @@
+use anyhow::anyhow;
+
+use crate::diff::Change;
+use crate::{lua, Space};
+
+impl Change {
+    // Changes that can be applied to a space with data without rewriting tuples
+    pub fn is_safe(&self) -> bool {
+        match self {
+            Change::SpaceAdded => true,
+            Change::FieldAdded { field, .. } => field.is_nullable == Some(true),
+            Change::FieldNullableChanged { declared, .. } => *declared,
+            Change::IndexAdded { .. } => true,
+            // the primary index can't be dropped while the space has other indexes or data
+            Change::IndexRemoved { index } => index.id != 0,
+            Change::IndexAltered { .. } => true,
+            Change::SequenceAdded { .. } => true,
+            Change::EngineChanged { .. } |
+            Change::SequenceRemoved { .. } |
+            Change::SequenceAltered { .. } |
+            Change::FlagChanged { .. } |
+            Change::OptionChanged { .. } |
+            Change::FieldRemoved { .. } |
+            Change::FieldRenamed { .. } |
+            Change::FieldTypeChanged { .. } |
+            Change::FieldCollationChanged { .. } => false,
+        }
+    }
+}
+
+impl Space {
+    // Brings the live space to the declared state and returns the applied changes
+    pub fn alter(&self) -> Result<Vec<Change>, anyhow::Error> {
+        let changes = self.diff()?;
+        self.apply(&changes)?;
+        Ok(changes)
+    }
+
+    // Creates the space or its missing indexes, anything else that differs from the
+    // declaration is reported as a conflict. Returns true if the space was created.
+    pub fn ensure(&self) -> Result<bool, anyhow::Error> {
+        let changes = self.diff()?;
+        if changes.iter().any(|change| matches!(change, Change::SpaceAdded)) {
+            self.create()?;
+            return Ok(true);
+        }
+        let conflicts: Vec<Change> = changes.iter().filter(|change| !matches!(change, Change::IndexAdded { .. } | Change::SequenceAdded { .. })).cloned().collect();
+        if !conflicts.is_empty() {
+            return Err(crate::VerifyError { space: self.name.clone(), changes: conflicts }.into());
+        }
+        self.apply(&changes)?;
+        Ok(false)
+    }
+
+    // Sets a format the existing tuples already satisfy: fields with a changed type
+    // become 'any' and new fields nullable, so a data migration can convert them.
+    // Secondary indexes over such fields are dropped, `alter` builds them again.
+    pub fn relax(&self, changes: &[Change]) -> Result<(), anyhow::Error> {
+        let mut format = self.format.clone();
+        let mut relaxed = vec![];
+        for change in changes {
+            match change {
+                Change::FieldAdded { .. } |
+                Change::FieldRemoved { .. } |
+                Change::FieldRenamed { .. } |
+                Change::FieldNullableChanged { .. } => {},
+                Change::FieldTypeChanged { name, .. } => {
+                    if let Some((position, field)) = format.iter_mut().enumerate().find(|(_, field)| &field.name == name) {
+                        field.field_type = crate::FieldType::Any;
+                        field.is_nullable = Some(true);
+                        field.collation = None;
+                        relaxed.push((position as u32 + 1, name.clone()));
+                    }
+                },
+                change if !change.is_safe() => {
+                    return Err(anyhow!("Can't alter space '{}' in place: {}", self.name, change));
+                },
+                _ => {},
+            }
+        }
+        let live = crate::live::LiveSpace::find(&self.name)?.ok_or(anyhow!("Can't find space '{}'", self.name))?;
+        for index in &live.indexes {
+            let field = match relaxed.iter().find(|(field_index, _)| index.parts.iter().any(|part| part.field_index == *field_index)) {
+                Some((_, field)) => field,
+                None => continue,
+            };
+            if index.id == 0 {
+                return Err(anyhow!("Can't change type of field '{}' of space '{}': it's a part of primary index '{}'", field, self.name, index.name));
+            }
+            log::info!("space '{}': dropping index '{}' over field '{}' for data migration", self.name, index.name, field);
+            lua::exec(&format!("{}:drop()", lua::index(&self.name, &index.name)))
+                .map_err(|err| anyhow!("Can't drop index '{}' of space '{}': {}", index.name, self.name, err))?;
+        }
+        for (position, field) in format.iter_mut().enumerate() {
+            let live_nullable = live.format.get(position).map(|field| field.is_nullable).unwrap_or(true);
+            if live_nullable {
+                field.is_nullable = Some(true);
+            }
+        }
+        log::info!("space '{}': relaxing format for data migration", self.name);
+        lua::exec(&format!("{}:format({})", lua::space(&self.name), lua::format(&format)))
+            .map_err(|err| anyhow!("Can't update format of space '{}': {}", self.name, err))
+    }
+
+    // Sets the declared format, tarantool checks every tuple against it
+    pub fn set_format(&self) -> Result<(), anyhow::Error> {
+        log::info!("space '{}': updating format", self.name);
+        lua::exec(&format!("{}:format({})", lua::space(&self.name), lua::format(&self.format)))
+            .map_err(|err| anyhow!("Can't update format of space '{}': {}", self.name, err))
+    }
+
+    pub fn apply(&self, changes: &[Change]) -> Result<(), anyhow::Error> {
+        let unsafe_changes: Vec<String> = changes.iter().filter(|change| !change.is_safe()).map(|change| format!("{}", change)).collect();
+        if !unsafe_changes.is_empty() {
+            return Err(anyhow!("Can't alter space '{}' in place:\n  - {}", self.name, unsafe_changes.join("\n  - ")));
+        }
+
+        if changes.iter().any(|change| matches!(change, Change::SpaceAdded)) {
+            return self.create();
+        }
+
+        let format_changed = changes.iter().any(|change| matches!(change, Change::FieldAdded { .. } | Change::FieldNullableChanged { .. }));
+        if format_changed {
+            self.set_format()?;
+        }
+
+        for change in changes {
+            if let Change::IndexRemoved { index } = change {
+                log::info!("space '{}': dropping index '{}'", self.name, index.name);
+                lua::exec(&format!("{}:drop()", lua::index(&self.name, &index.name)))
+                    .map_err(|err| anyhow!("Can't drop index '{}' of space '{}': {}", index.name, self.name, err))?;
+            }
+        }
+        for change in changes {
+            if let Change::IndexAltered { index, .. } = change {
+                log::info!("space '{}': altering index '{}'", self.name, index.name);
+                lua::exec(&format!("{}:alter({})", lua::index(&self.name, &index.name), lua::index_options(index)))
+                    .map_err(|err| anyhow!("Can't alter index '{}' of space '{}': {}", index.name, self.name, err))?;
+            }
+        }
+        let space = self.space()?;
+        for change in changes {
+            if let Change::IndexAdded { index } = change {
+                log::info!("space '{}': creating index '{}'", self.name, index.name);
+                space.create_index(&index.name, &index.options())
+                    .map_err(|err| anyhow!("Can't create index '{}' of space '{}': {}", index.name, self.name, err))?;
+            }
+        }
+        if changes.iter().any(|change| matches!(change, Change::SequenceAdded { .. })) {
+            log::info!("space '{}': creating sequence", self.name);
+            self.create_sequence()?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::anyhow;
+use serde::{Serialize, Deserialize};
+use tarantool::index::IteratorType;
+
+use crate::FieldType;
+use crate::live::LiveSpace;
+use crate::migrations::{internal_space, Transform};
+use crate::transaction::transaction;
+
+pub const PROGRESS_SPACE_NAME: &str = "_tarantool_schema_migration_progress";
+
+const LOG_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Progress {
+    version: u64,
+    space: String,
+    last_key: Vec<rmpv::Value>,
+    processed: u64,
+}
+impl tarantool::tuple::AsTuple for Progress {}
+
+fn progress_space() -> crate::Space {
+    internal_space(PROGRESS_SPACE_NAME, vec![
+        ("version", FieldType::Unsigned),
+        ("space", FieldType::String),
+        ("last_key", FieldType::Array),
+        ("processed", FieldType::Unsigned),
+    ])
+}
+
+// Rewrites every tuple of the space in primary key order, `batch_size` tuples per
+// transaction, yielding between batches. The last processed key is committed with
+// each batch, so an interrupted migration continues where it stopped.
+pub(crate) fn run(version: u64, transform: &Transform) -> Result<u64, anyhow::Error> {
+    let live = LiveSpace::find(&transform.space)?
+        .ok_or(anyhow!("Can't find space '{}' for migration transform", transform.space))?;
+    let primary = live.indexes.iter().find(|index| index.id == 0)
+        .ok_or(anyhow!("Space '{}' has no primary index", transform.space))?;
+    let key_fields: Vec<usize> = primary.parts.iter().map(|part| part.field_index as usize - 1).collect();
+
+    progress_space().alter()?;
+    let mut progress_space = progress_space().space()?;
+    let mut space = tarantool::space::Space::find(&transform.space)
+        .ok_or(anyhow!("Can't find space '{}'", transform.space))?;
+
+    let mut progress = match progress_space.get(&(version,))? {
+        Some(tuple) => {
+            let progress = tuple.as_struct::<Progress>()?;
+            log::info!("migration {}: resuming space '{}' after {} tuples", version, transform.space, progress.processed);
+            progress
+        },
+        None => Progress { version, space: transform.space.clone(), last_key: vec![], processed: 0 },
+    };
+
+    let mut logged_at = std::time::Instant::now();
+    loop {
+        let tuples: Vec<tarantool::tuple::Tuple> = if progress.last_key.is_empty() {
+            space.primary_key().select(IteratorType::All, &())?.take(transform.batch_size).collect()
+        } else {
+            space.primary_key().select(IteratorType::GT, &progress.last_key)?.take(transform.batch_size).collect()
+        };
+        let last = match tuples.last() {
+            Some(last) => last.as_struct::<Vec<rmpv::Value>>()?,
+            None => break,
+        };
+        let mut last_key = vec![];
+        for field in &key_fields {
+            last_key.push(last.get(*field).cloned().ok_or(anyhow!("Tuple of space '{}' has no key field #{}", transform.space, field + 1))?);
+        }
+
+        transaction(|| {
+            for tuple in &tuples {
+                if let Some(tuple) = (transform.function)(tuple)? {
+                    space.replace(&tuple)?;
+                }
+            }
+            progress.last_key = last_key;
+            progress.processed += tuples.len() as u64;
+            progress_space.replace(&progress)?;
+            Ok(())
+        })?;
+
+        if logged_at.elapsed() >= LOG_INTERVAL {
+            log::info!("migration {}: {} tuples of space '{}' processed", version, progress.processed, transform.space);
+            logged_at = std::time::Instant::now();
+        }
+        tarantool::fiber::reschedule();
+    }
+
+    Ok(progress.processed)
+}
+
+// Called in the transaction that records the migration as applied
+pub(crate) fn finish(version: u64) -> Result<(), anyhow::Error> {
+    if let Some(mut space) = tarantool::space::Space::find(PROGRESS_SPACE_NAME) {
+        space.delete(&(version,))?;
+    }
+    Ok(())
+}
@@ -15,6 +15,8 @@ pub(crate) fn generate(output_path: &std::path::Path, crate_name: &str, schema:
     src += &format!("log = {{ version = \"0.4\" }}\n");
     src += &format!("parking_lot = {{ version = \"0.11\" }}\n");
     src += &format!("once_cell = {{ version = \"1.8\" }}\n");
+    src += &format!("rmpv = {{ version = \"1\", features = [\"with-serde\"] }}\n");
+    src += &format!("serde_bytes = {{ version = \"0.11\" }}\n");
     src += &format!("\n");
 
     src += &match tarantool {
@@ -0,0 +1,45 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collation {
+    #[serde(skip)]
+    pub name: String,
+
+    pub locale: String,
+
+    // ICU options: strength, numeric_collation, case_first, ...
+    #[serde(flatten)]
+    pub options: linked_hash_map::LinkedHashMap<String, String>,
+}
+impl Collation {
+    // Collations every tarantool instance has: none, binary, unicode, unicode_ci
+    // and the per-locale unicode_<locale>_s1..s3
+    pub fn is_builtin(name: &str) -> bool {
+        match name {
+            "none" | "binary" | "unicode" | "unicode_ci" => true,
+            name => name.starts_with("unicode_") && (name.ends_with("_s1") || name.ends_with("_s2") || name.ends_with("_s3")),
+        }
+    }
+
+    pub(crate) fn from_live(live: &crate::live::LiveCollation) -> Self {
+        Collation {
+            name: live.name.clone(),
+            locale: live.locale.clone(),
+            options: live.options.iter().cloned().collect(),
+        }
+    }
+
+    pub fn codegen(&self) -> String {
+        let mut str = format!("");
+        str += &format!("        tarantool_schema::Collation {{\n");
+        str += &format!("            name: {:?}.to_string(),\n", self.name);
+        str += &format!("            locale: {:?}.to_string(),\n", self.locale);
+        str += &format!("            options: vec![\n");
+        for (name, value) in &self.options {
+            str += &format!("                ({:?}.to_string(), {:?}.to_string()),\n", name, value);
+        }
+        str += &format!("            ],\n");
+        str += &format!("        }},\n");
+        str
+    }
+}
@@ -12,19 +12,472 @@ pub struct Field {
     pub name: String,
 
     #[serde(default = "Field::default_is_nullable")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub is_nullable: Option<bool>,
 
     #[serde(rename = "type")]
-    pub field_type: tarantool::space::SpaceFieldType,
+    pub field_type: crate::FieldType,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collation: Option<String>,
+
+    // Row overrides: a domain type built `From` the generated one (`UserId(u64)`),
+    // a `#[serde(with)]` module and the Rust name of the field. The tuple layout stays the same.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rust_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serde_with: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rename: Option<String>,
+
+    // `string` or `unsigned` field holding one of the names, the Row gets a Rust enum.
+    // Unsigned fields store the position of the name.
+    #[serde(rename = "enum")]
+    #[serde(default = "Field::default_enum_values")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub enum_values: Vec<String>,
+
+    // nested structure of `array` and `map` fields: `items: unsigned`, `fields: {city: string}`.
+    // Index part paths are checked against it and the Row gets `Vec<T>` and nested structs.
+    #[serde(default = "Field::default_items")]
+    #[serde(deserialize_with = "Field::deserialize_items")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Field>>,
+    #[serde(default = "Field::default_fields")]
+    #[serde(deserialize_with = "Field::deserialize_fields")]
+    #[serde(skip_serializing_if = "linked_hash_map::LinkedHashMap::is_empty")]
+    pub fields: linked_hash_map::LinkedHashMap<String, Field>,
+}
+
+// a nested field is declared with its type alone or in full
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Declaration {
+    Type(crate::FieldType),
+    Field(Field),
+}
+impl Declaration {
+    fn field(self) -> Field {
+        match self {
+            Declaration::Type(field_type) => Field::nested(field_type),
+            Declaration::Field(field) => field,
+        }
+    }
 }
+
 impl Field {
     fn default_is_nullable() -> Option<bool> { None }
+    fn default_enum_values() -> Vec<String> { vec![] }
+    fn default_items() -> Option<Box<Field>> { None }
+    fn default_fields() -> linked_hash_map::LinkedHashMap<String, Field> { linked_hash_map::LinkedHashMap::new() }
+
+    fn deserialize_items<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Box<Field>>, D::Error> {
+        Ok(Some(Box::new(Declaration::deserialize(deserializer)?.field())))
+    }
+    fn deserialize_fields<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<linked_hash_map::LinkedHashMap<String, Field>, D::Error> {
+        let declarations = linked_hash_map::LinkedHashMap::<String, Declaration>::deserialize(deserializer)?;
+        Ok(declarations.into_iter().map(|(name, declaration)| (name, declaration.field())).collect())
+    }
+
+    // names the nested fields and checks they are declared on the right types
+    pub(crate) fn validate_nested(&mut self, path: &str) -> Result<(), anyhow::Error> {
+        if self.items.is_some() && self.field_type != crate::FieldType::Array {
+            return Err(anyhow::anyhow!("Field '{}' declares items but its type is {}, not array", path, self.field_type));
+        }
+        if !self.fields.is_empty() && self.field_type != crate::FieldType::Map {
+            return Err(anyhow::anyhow!("Field '{}' declares fields but its type is {}, not map", path, self.field_type));
+        }
+        if !self.enum_values.is_empty() {
+            if !matches!(self.field_type, crate::FieldType::String | crate::FieldType::Unsigned) {
+                return Err(anyhow::anyhow!("Field '{}' declares enum but its type is {}, not string or unsigned", path, self.field_type));
+            }
+            let mut variants: Vec<String> = vec![];
+            for value in &self.enum_values {
+                let variant = Field::variant_name(value);
+                if variants.contains(&variant) {
+                    return Err(anyhow::anyhow!("Enum value '{}' of field '{}' becomes variant '{}' like another value", value, path, variant));
+                }
+                variants.push(variant);
+            }
+        }
+        for nested in self.items.iter().map(|items| items.as_ref()).chain(self.fields.values()) {
+            if nested.rust_type.is_some() || nested.serde_with.is_some() || nested.rename.is_some() {
+                return Err(anyhow::anyhow!("Field '{}' has a nested field with rust_type, serde_with or rename, they are supported only on Row fields", path));
+            }
+        }
+        if let Some(items) = &mut self.items {
+            items.validate_nested(&format!("{}[*]", path))?;
+        }
+        for (name, field) in &mut self.fields {
+            if !is_identifier(name) {
+                return Err(anyhow::anyhow!("Field '{}.{}' can't be a field of the generated struct, its name isn't a Rust identifier", path, name));
+            }
+            field.name = name.clone();
+            field.validate_nested(&format!("{}.{}", path, name))?;
+        }
+        Ok(())
+    }
+
+    // Names of the types `base_rust_type` generates for the field
+    pub(crate) fn type_names(&self, struct_name: &str, names: &mut Vec<String>) {
+        match self.field_type {
+            crate::FieldType::Unsigned | crate::FieldType::String if !self.enum_values.is_empty() => {
+                names.push(struct_name.to_string());
+            },
+            crate::FieldType::Array => {
+                if let Some(items) = &self.items {
+                    items.type_names(&format!("{}Item", struct_name), names);
+                }
+            },
+            crate::FieldType::Map if !self.fields.is_empty() => {
+                names.push(struct_name.to_string());
+                for (name, field) in &self.fields {
+                    field.type_names(&Field::struct_name(struct_name, name), names);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    // Rust type without the `Option` of a nullable field. Map fields with declared
+    // `fields` become structs named `struct_name`, their code is appended to `structs`.
+    pub(crate) fn base_rust_type(&self, struct_name: &str, structs: &mut String) -> Result<String, anyhow::Error> {
+        use crate::FieldType;
+        Ok(match self.field_type {
+            FieldType::Unsigned | FieldType::String if !self.enum_values.is_empty() => {
+                self.codegen_enum(struct_name, structs);
+                struct_name.to_string()
+            },
+            FieldType::Unsigned => format!("u64"),
+            FieldType::String => format!("String"),
+            FieldType::Number => format!("tarantool_schema::types::Number"),
+            FieldType::Double => format!("f64"),
+            FieldType::Integer => format!("i64"),
+            FieldType::Boolean => format!("bool"),
+            FieldType::Decimal => format!("tarantool::decimal::Decimal"),
+            FieldType::Uuid => format!("tarantool::uuid::Uuid"),
+            // `#[serde(with)]` can't reach into a Vec, nested bytes use ByteBuf
+            FieldType::Varbinary => format!("serde_bytes::ByteBuf"),
+            FieldType::Array => match &self.items {
+                Some(items) => format!("Vec<{}>", items.rust_type(&format!("{}Item", struct_name), structs)?),
+                None => format!("Vec<rmpv::Value>"),
+            },
+            FieldType::Map if self.fields.is_empty() => format!("std::collections::HashMap<String, rmpv::Value>"),
+            FieldType::Map => {
+                self.codegen_struct(struct_name, structs)?;
+                struct_name.to_string()
+            },
+            FieldType::Any | FieldType::Scalar => format!("rmpv::Value"),
+            FieldType::Datetime => format!("tarantool_schema::types::Datetime"),
+            FieldType::Interval => format!("tarantool_schema::types::Interval"),
+        })
+    }
+    pub(crate) fn rust_type(&self, struct_name: &str, structs: &mut String) -> Result<String, anyhow::Error> {
+        let rust_type = self.base_rust_type(struct_name, structs)?;
+        Ok(match self.is_nullable {
+            Some(true) => format!("Option<{}>", rust_type),
+            _ => rust_type,
+        })
+    }
+    pub(crate) fn struct_name(parent: &str, name: &str) -> String {
+        format!("{}{}", parent, name.to_case(Case::Pascal))
+    }
+
+    // the value in Pascal case without the characters an identifier can't have,
+    // `Value` goes before one that starts with a digit or is a keyword: "1" -> Value1
+    pub(crate) fn variant_name(value: &str) -> String {
+        let variant: String = value.to_case(Case::Pascal).chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        match is_identifier(&variant) {
+            true => variant,
+            false => format!("Value{}", variant),
+        }
+    }
+    // `None` when the value isn't one of the enum
+    pub(crate) fn enum_literal(&self, struct_name: &str, value: &str) -> Option<String> {
+        match self.enum_values.iter().any(|name| name == value) {
+            true => Some(format!("{}::{}", struct_name, Field::variant_name(value))),
+            false => None,
+        }
+    }
+
+    fn codegen_enum(&self, struct_name: &str, structs: &mut String) {
+        let mut str = format!("\n");
+        match self.field_type {
+            crate::FieldType::Unsigned => {
+                str += &format!("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n");
+                str += &format!("pub enum {} {{\n", struct_name);
+                for (position, value) in self.enum_values.iter().enumerate() {
+                    str += &format!("    {} = {},\n", Field::variant_name(value), position);
+                }
+                str += &format!("}}\n");
+                // stored as the position of the name
+                str += &format!("impl serde::Serialize for {} {{\n", struct_name);
+                str += &format!("    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{ serializer.serialize_u64(*self as u64) }}\n");
+                str += &format!("}}\n");
+                str += &format!("impl<'de> serde::Deserialize<'de> for {} {{\n", struct_name);
+                str += &format!("    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{\n");
+                str += &format!("        match <u64 as serde::Deserialize>::deserialize(deserializer)? {{\n");
+                for (position, value) in self.enum_values.iter().enumerate() {
+                    str += &format!("            {} => Ok({}::{}),\n", position, struct_name, Field::variant_name(value));
+                }
+                str += &format!("            value => Err(<D::Error as serde::de::Error>::custom(format!(\"unknown {} value {{}}\", value))),\n", struct_name);
+                str += &format!("        }}\n");
+                str += &format!("    }}\n");
+                str += &format!("}}\n");
+            },
+            _ => {
+                str += &format!("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]\n");
+                str += &format!("pub enum {} {{\n", struct_name);
+                for value in &self.enum_values {
+                    str += &format!("    #[serde(rename = {:?})]\n", value);
+                    str += &format!("    {},\n", Field::variant_name(value));
+                }
+                str += &format!("}}\n");
+            },
+        }
+        *structs += &str;
+    }
+
+    fn codegen_struct(&self, struct_name: &str, structs: &mut String) -> Result<(), anyhow::Error> {
+        let mut fields = vec![];
+        for (name, field) in &self.fields {
+            fields.push((name, field.rust_type(&Field::struct_name(struct_name, name), structs)?));
+        }
+        let mut str = format!("\n");
+        str += &format!("#[derive(Debug, Clone, serde::Deserialize)]\n");
+        str += &format!("pub struct {} {{\n", struct_name);
+        for (name, rust_type) in &fields {
+            str += &format!("    pub {}: {},\n", name, rust_type);
+        }
+        str += &format!("}}\n");
+        // a MessagePack map: derived Serialize would write an array like for the Row
+        str += &format!("impl serde::Serialize for {} {{\n", struct_name);
+        str += &format!("    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{\n");
+        str += &format!("        use serde::ser::SerializeMap;\n");
+        str += &format!("        let mut map = serializer.serialize_map(Some({}))?;\n", fields.len());
+        for (name, _) in &fields {
+            str += &format!("        map.serialize_entry({:?}, &self.{})?;\n", name, name);
+        }
+        str += &format!("        map.end()\n");
+        str += &format!("    }}\n");
+        str += &format!("}}\n");
+        *structs += &str;
+        Ok(())
+    }
+
+    pub(crate) fn from_live(live: &crate::live::LiveField) -> Result<Self, anyhow::Error> {
+        Ok(Field {
+            id: 0,
+            name: live.name.clone(),
+            is_nullable: if live.is_nullable { Some(true) } else { None },
+            field_type: super::schema::parse_type(&live.field_type)?,
+            collation: live.collation.clone(),
+            rust_type: None,
+            serde_with: None,
+            // fields made by hand in Lua can be named `type` or `end-date`
+            rename: if is_identifier(&live.name) { None } else { Some(identifier(&live.name)) },
+            enum_values: vec![],
+            items: None,
+            fields: linked_hash_map::LinkedHashMap::new(),
+        })
+    }
 
     pub fn const_name(&self) -> String {
-        format!("FIELD__{}", self.name.clone().to_case(Case::ScreamingSnake))
+        format!("FIELD__{}", self.name().to_case(Case::ScreamingSnake))
+    }
+    // `tarantool_schema::Field` literal for the generated code
+    pub fn runtime(&self, name: &str, indent: &str) -> String {
+        let mut str = format!("tarantool_schema::Field {{\n");
+        str += &format!("{}    name: {},\n", indent, name);
+        str += &format!("{}    field_type: tarantool_schema::FieldType::{:?},\n", indent, self.field_type);
+        str += &format!("{}    is_nullable: {:?},\n", indent, self.is_nullable);
+        str += &format!("{}    collation: {},\n", indent, self.collation.as_ref().map(|collation| format!("Some({:?}.to_string())", collation)).unwrap_or("None".to_string()));
+        str += &format!("{}}}", indent);
+        str
+    }
+    // Walks a JSON path of an index part (`tags[*]`, `address.city`, `[2]["key"]`)
+    // through the declared `items` / `fields` and returns the field at its end
+    pub fn path_field(&self, path: &str) -> Result<&Field, anyhow::Error> {
+        let mut field = self;
+        for step in parse_path(path)? {
+            field = match step {
+                PathStep::Key(key) => {
+                    if field.field_type != crate::FieldType::Map {
+                        return Err(anyhow::anyhow!("'{}' is a key but the field is {}, not map", key, field.field_type));
+                    }
+                    field.fields.get(&key).ok_or(anyhow::anyhow!("map has no declared field '{}'", key))?
+                },
+                PathStep::Item => {
+                    if field.field_type != crate::FieldType::Array {
+                        return Err(anyhow::anyhow!("array element is taken but the field is {}, not array", field.field_type));
+                    }
+                    field.items.as_deref().ok_or(anyhow::anyhow!("array has no declared items"))?
+                },
+            };
+        }
+        Ok(field)
+    }
+    // Declares the structure a live index path walks through, so an introspected
+    // schema validates: tarantool itself keeps no nested declarations
+    pub(crate) fn declare_path(&mut self, path: &str, leaf_type: crate::FieldType) -> Result<(), anyhow::Error> {
+        let steps = parse_path(path)?;
+        let mut field = self;
+        for (position, step) in steps.iter().enumerate() {
+            let field_type = if position + 1 == steps.len() { leaf_type } else {
+                match steps[position + 1] {
+                    PathStep::Key(_) => crate::FieldType::Map,
+                    PathStep::Item => crate::FieldType::Array,
+                }
+            };
+            field = match step {
+                PathStep::Key(key) if field.field_type == crate::FieldType::Map => {
+                    field.fields.entry(key.clone()).or_insert_with(|| Field::nested(field_type))
+                },
+                PathStep::Item if field.field_type == crate::FieldType::Array => {
+                    field.items.get_or_insert_with(|| Box::new(Field::nested(field_type)))
+                },
+                _ => return Ok(()),
+            };
+        }
+        Ok(())
     }
+    fn nested(field_type: crate::FieldType) -> Self {
+        Field {
+            id: 0,
+            name: format!(""),
+            is_nullable: None,
+            field_type,
+            collation: None,
+            rust_type: None,
+            serde_with: None,
+            rename: None,
+            enum_values: vec![],
+            items: None,
+            fields: linked_hash_map::LinkedHashMap::new(),
+        }
+    }
+    // name of the Row field
     pub fn name(&self) -> String {
         // self.name.clone().to_case(Case::Snake)
-        self.name.clone()
+        self.rename.clone().unwrap_or(self.name.clone())
+    }
+}
+// Types the generated space module declares or imports
+pub(crate) const RESERVED_TYPE_NAMES: &[&str] = &[
+    "Row", "Space", "SpaceCreateOptions", "SpaceFieldFormat", "SpaceFieldType",
+    "IndexType", "IndexOptions", "IndexPart", "IndexFieldType", "IteratorType", "AsTuple", "Tuple",
+];
+
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+    "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+    "trait", "true", "type", "unsafe", "use", "where", "while", "async", "await", "dyn", "abstract", "become", "box",
+    "do", "final", "macro", "override", "priv", "typeof", "unsized", "virtual", "yield", "try",
+];
+
+pub(crate) fn is_identifier(name: &str) -> bool {
+    name.chars().next().map(|c| c.is_ascii_alphabetic() || c == '_').unwrap_or(false)
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+        && !KEYWORDS.contains(&name)
+}
+
+// closest identifier to a name: "end-date" -> end_date, "type" -> type_, "1st" -> _1st
+fn identifier(name: &str) -> String {
+    let mut identifier: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if identifier.chars().next().map(|c| c.is_ascii_digit()).unwrap_or(true) {
+        identifier = format!("_{}", identifier);
     }
-}
\ No newline at end of file
+    if !is_identifier(&identifier) {
+        identifier.push('_');
+    }
+    identifier
+}
+
+enum PathStep {
+    Key(String),
+    // `[N]` and `[*]`, array items share one declaration
+    Item,
+}
+
+fn parse_path(path: &str) -> Result<Vec<PathStep>, anyhow::Error> {
+    let mut steps = vec![];
+    let mut chars = path.chars().peekable();
+    let mut first = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                let mut key = format!("");
+                while let Some(c) = chars.peek() {
+                    if *c == '.' || *c == '[' { break; }
+                    key.push(chars.next().unwrap());
+                }
+                if key.is_empty() {
+                    return Err(anyhow::anyhow!("empty key in path '{}'", path));
+                }
+                steps.push(PathStep::Key(key));
+            },
+            '[' => {
+                let mut inner = format!("");
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err(anyhow::anyhow!("unclosed '[' in path '{}'", path)),
+                    }
+                }
+                let quoted = inner.len() >= 2 && ((inner.starts_with('"') && inner.ends_with('"')) || (inner.starts_with('\'') && inner.ends_with('\'')));
+                if quoted {
+                    steps.push(PathStep::Key(inner[1..inner.len() - 1].to_string()));
+                } else if inner == "*" || (!inner.is_empty() && inner.chars().all(|c| c.is_ascii_digit()) && inner != "0") {
+                    steps.push(PathStep::Item);
+                } else {
+                    return Err(anyhow::anyhow!("'[{}]' isn't an array index, '*' or a quoted key in path '{}'", inner, path));
+                }
+            },
+            c if first => {
+                // a leading key doesn't need a dot: `address.city`
+                let mut key = format!("{}", c);
+                while let Some(c) = chars.peek() {
+                    if *c == '.' || *c == '[' { break; }
+                    key.push(chars.next().unwrap());
+                }
+                steps.push(PathStep::Key(key));
+            },
+            c => return Err(anyhow::anyhow!("unexpected '{}' in path '{}'", c, path)),
+        }
+        first = false;
+    }
+    if steps.is_empty() {
+        return Err(anyhow::anyhow!("path is empty"));
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(path: &str) -> Vec<String> {
+        parse_path(path).unwrap().into_iter().map(|step| match step {
+            PathStep::Key(key) => key,
+            PathStep::Item => format!("[*]"),
+        }).collect()
+    }
+
+    #[test]
+    fn parse_path_reads_keys_and_items() {
+        assert_eq!(steps("address.city"), vec!["address", "city"]);
+        assert_eq!(steps(".address"), vec!["address"]);
+        assert_eq!(steps("tags[*]"), vec!["tags", "[*]"]);
+        assert_eq!(steps("[2][\"key\"]"), vec!["[*]", "key"]);
+        assert_eq!(steps("points[1]['x y'].z"), vec!["points", "[*]", "x y", "z"]);
+    }
+
+    #[test]
+    fn parse_path_rejects_malformed_paths() {
+        for path in ["", "a..b", "a.", "tags[", "tags[0]", "tags[x]", "tags[]", "[*]x"] {
+            assert!(parse_path(path).is_err(), "{}", path);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::anyhow;
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Lua,
+    // a function exported from a module, the name is `module.symbol`
+    C,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Function {
+    #[serde(skip)]
+    pub name: String,
+
+    #[serde(default = "Function::default_language")]
+    pub language: Language,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+
+    #[serde(default = "Function::default_false")]
+    pub is_deterministic: bool,
+    #[serde(default = "Function::default_false")]
+    pub is_sandboxed: bool,
+    // the function returns an array of keys
+    #[serde(default = "Function::default_false")]
+    pub is_multikey: bool,
+}
+impl Function {
+    fn default_language() -> Language { Language::Lua }
+    fn default_false() -> bool { false }
+
+    pub fn validate(mut self, name: String) -> Result<Self, anyhow::Error> {
+        self.name = name;
+        match self.language {
+            Language::Lua => {
+                if self.body.is_none() {
+                    return Err(anyhow!("Lua function '{}' has no body", self.name));
+                }
+            },
+            Language::C => {
+                if self.body.is_some() {
+                    return Err(anyhow!("C function '{}' can't have a body, it's loaded from a module", self.name));
+                }
+                if self.is_sandboxed {
+                    return Err(anyhow!("C function '{}' can't be sandboxed, only Lua functions can", self.name));
+                }
+            },
+        }
+        Ok(self)
+    }
+
+    pub(crate) fn from_live(live: &crate::live::LiveFunction) -> Result<Self, anyhow::Error> {
+        Ok(Function {
+            name: live.name.clone(),
+            language: super::schema::parse_type(&live.language)?,
+            body: live.body.clone(),
+            is_deterministic: live.is_deterministic,
+            is_sandboxed: live.is_sandboxed,
+            is_multikey: live.is_multikey,
+        })
+    }
+
+    pub fn codegen(&self) -> String {
+        let mut str = format!("");
+        str += &format!("        tarantool_schema::Function {{\n");
+        str += &format!("            name: {:?}.to_string(),\n", self.name);
+        str += &format!("            language: {:?}.to_string(),\n", format!("{:?}", self.language).to_lowercase());
+        str += &format!("            body: {},\n", self.body.as_ref().map(|body| format!("Some({:?}.to_string())", body)).unwrap_or("None".to_string()));
+        str += &format!("            is_deterministic: {},\n", self.is_deterministic);
+        str += &format!("            is_sandboxed: {},\n", self.is_sandboxed);
+        str += &format!("            is_multikey: {},\n", self.is_multikey);
+        str += &format!("        }},\n");
+        str
+    }
+}
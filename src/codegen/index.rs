@@ -8,10 +8,15 @@ pub struct IndexPart {
 
     #[serde(rename = "type")]
     pub index_field_type: tarantool::index::IndexFieldType,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub is_nullable: Option<bool>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collation: Option<String>,
+
     #[serde(skip)]
     pub field: Option<super::field::Field>,
     #[serde(skip)]
@@ -19,7 +24,11 @@ pub struct IndexPart {
 }
 impl IndexPart {
     pub fn const_path(&self) -> String {
-        self.field.clone().unwrap().const_name()
+        match &self.field {
+            Some(field) => field.const_name(),
+            // parts of a functional index name the fields of the key returned by the function
+            None => format!("{:?}", self.field_name),
+        }
     }
 }
 
@@ -36,27 +45,136 @@ pub struct Index {
     pub unique: bool,
 
     pub parts: Vec<IndexPart>,
+
+    // vinyl only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bloom_fpr: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range_size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_count_per_level: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_size_ratio: Option<f32>,
+
+    // functional index: name of a function from the `functions` section
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub func: Option<String>,
+    // rtree only, tarantool defaults to 2 and euclid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimension: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<tarantool::index::RtreeIndexDistanceType>,
 }
 impl Index {
     fn default_index_type() -> tarantool::index::IndexType { tarantool::index::IndexType::Tree }
     fn default_unique() -> bool { false }
 
+    pub(crate) fn from_live(live: &crate::live::LiveIndex, format: &[super::field::Field]) -> Result<Self, anyhow::Error> {
+        let mut parts = vec![];
+        for part in &live.parts {
+            if live.func.is_some() {
+                parts.push(IndexPart {
+                    field_name: format!("key_{}", part.field_index),
+                    index_field_type: super::schema::parse_type(&part.field_type)?,
+                    is_nullable: if part.is_nullable { Some(true) } else { None },
+                    path: part.path.clone(),
+                    collation: part.collation.clone(),
+                    field: None,
+                    part: None,
+                });
+                continue;
+            }
+            let field = format.get(part.field_index as usize - 1)
+                .ok_or(anyhow::anyhow!("Index '{}' refers to field #{} which isn't in the format", live.name, part.field_index))?;
+            parts.push(IndexPart {
+                field_name: field.name.clone(),
+                index_field_type: super::schema::parse_type(&part.field_type)?,
+                is_nullable: if part.is_nullable { Some(true) } else { None },
+                path: part.path.clone(),
+                // inherited from the field when the same
+                collation: if part.collation == field.collation { None } else { part.collation.clone() },
+                field: None,
+                part: None,
+            });
+        }
+        Ok(Index {
+            name: live.name.clone(),
+            index_type: super::schema::parse_type(&live.index_type)?,
+            unique: live.unique,
+            parts,
+            bloom_fpr: live.bloom_fpr.map(|value| value as f32),
+            page_size: live.page_size.map(|value| value as u32),
+            range_size: live.range_size.map(|value| value as u32),
+            run_count_per_level: live.run_count_per_level.map(|value| value as u32),
+            run_size_ratio: live.run_size_ratio.map(|value| value as f32),
+            func: live.func.clone(),
+            dimension: live.dimension.map(|dimension| dimension as u32),
+            distance: match &live.distance {
+                Some(distance) => Some(super::schema::parse_type(distance)?),
+                None => None,
+            },
+        })
+    }
+
     pub fn const_name(&self) -> String {
         format!("INDEX__{}", self.name.clone().to_case(Case::ScreamingSnake))
     }
     pub fn name(&self) -> String { self.name.clone() }
+
+    pub fn dimension(&self) -> u32 { self.dimension.unwrap_or(2) }
+
+    pub(crate) fn has_vinyl_options(&self) -> bool {
+        self.bloom_fpr.is_some() || self.page_size.is_some() || self.range_size.is_some() || self.run_count_per_level.is_some() || self.run_size_ratio.is_some()
+    }
 }
 
-// SpaceFieldType          IndexFieldType
-//      Any           -         -
-//      -             -      Varbinary
-//      Unsigned      -      Unsigned
-//      String        -      String
-//      Number        -      Number
-//      Double        -      Double
-//      Integer       -      Integer
-//      Boolean       -      Boolean
-//      Decimal       -      Decimal
-//      Uuid          -      Uuid
-//      Array         -      Array
-//      Scalar        -      Scalar
\ No newline at end of file
+// Tarantool accepts an index part when its type and the field type are the same
+// or one contains the other, the narrower one is then enforced:
+//   any > scalar > number > integer > unsigned
+//         scalar > number > double, decimal
+//         scalar > string, boolean, varbinary, uuid
+// array, map, datetime and interval contain only themselves.
+fn type_contains(outer: &str, inner: &str) -> bool {
+    outer == inner || match outer {
+        "any" => true,
+        "scalar" => !matches!(inner, "any" | "array" | "map" | "datetime" | "interval"),
+        "number" => matches!(inner, "integer" | "unsigned" | "double" | "decimal"),
+        "integer" => inner == "unsigned",
+        _ => false,
+    }
+}
+pub(crate) fn compatible(field_type: &str, part_type: &str) -> bool {
+    type_contains(field_type, part_type) || type_contains(part_type, field_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_contains_follows_hierarchy() {
+        assert!(type_contains("any", "map"));
+        assert!(type_contains("scalar", "uuid"));
+        assert!(type_contains("number", "decimal"));
+        assert!(type_contains("integer", "unsigned"));
+        assert!(type_contains("string", "string"));
+        assert!(!type_contains("scalar", "array"));
+        assert!(!type_contains("unsigned", "integer"));
+        assert!(!type_contains("number", "string"));
+        assert!(!type_contains("datetime", "scalar"));
+    }
+
+    #[test]
+    fn compatible_works_both_ways() {
+        assert!(compatible("unsigned", "integer"));
+        assert!(compatible("integer", "unsigned"));
+        assert!(compatible("double", "scalar"));
+        assert!(compatible("any", "string"));
+        assert!(!compatible("varbinary", "integer"));
+        assert!(!compatible("string", "unsigned"));
+        assert!(!compatible("double", "integer"));
+        assert!(!compatible("map", "scalar"));
+    }
+}
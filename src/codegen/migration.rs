@@ -0,0 +1,149 @@
+use anyhow::anyhow;
+use serde::{Serialize, Deserialize};
+
+use super::field::Field;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Down {
+    // runs over the space of the migration step
+    pub transform: Option<String>,
+
+    #[serde(default = "Down::default_drop_indexes")]
+    pub drop_indexes: linked_hash_map::LinkedHashMap<String, Vec<String>>,
+
+    #[serde(default = "Down::default_restore_format")]
+    pub restore_format: linked_hash_map::LinkedHashMap<String, linked_hash_map::LinkedHashMap<String, Field>>,
+}
+impl Down {
+    fn default_drop_indexes() -> linked_hash_map::LinkedHashMap<String, Vec<String>> { linked_hash_map::LinkedHashMap::new() }
+    fn default_restore_format() -> linked_hash_map::LinkedHashMap<String, linked_hash_map::LinkedHashMap<String, Field>> { linked_hash_map::LinkedHashMap::new() }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Migration {
+    pub version: u64,
+
+    #[serde(default = "Migration::default_description")]
+    pub description: String,
+
+    // optional keys are skipped when empty so adding new ones keeps old checksums
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub space: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transform: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<usize>,
+
+    // not a part of the checksum: an inverse can be declared after the step was applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub down: Option<Down>,
+}
+impl Migration {
+    fn default_description() -> String { format!("") }
+    fn default_batch_size() -> usize { 1000 }
+
+    // FNV-1a over the YAML of the step: stable between builds, unlike std's hasher
+    pub fn checksum(&self) -> Result<String, anyhow::Error> {
+        // every key of the step with its value, `down` excluded
+        let mut body = serde_yaml::to_value(self)?;
+        if let serde_yaml::Value::Mapping(mapping) = &mut body {
+            mapping.remove(&serde_yaml::Value::from("down"));
+        }
+        let yaml = serde_yaml::to_string(&body)?;
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in yaml.as_bytes() {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        Ok(format!("{:016x}", hash))
+    }
+
+    pub fn validate(mut self, spaces: &linked_hash_map::LinkedHashMap<String, super::space::Space>) -> Result<Self, anyhow::Error> {
+        match (&self.space, &self.transform) {
+            (Some(space), Some(_)) => {
+                if !spaces.contains_key(space) {
+                    return Err(anyhow!("Migration {} refers to unknown space '{}'", self.version, space));
+                }
+            },
+            (None, Some(transform)) => return Err(anyhow!("Migration {} has transform '{}' but no space", self.version, transform)),
+            (Some(space), None) => return Err(anyhow!("Migration {} has space '{}' but no transform", self.version, space)),
+            (None, None) if self.batch_size.is_some() => return Err(anyhow!("Migration {} has batch_size but no transform", self.version)),
+            (None, None) => {},
+        }
+        if self.batch_size == Some(0) {
+            return Err(anyhow!("Migration {} has zero batch_size", self.version));
+        }
+
+        if let Some(down) = &mut self.down {
+            if down.transform.is_some() && self.space.is_none() {
+                return Err(anyhow!("Migration {} has down transform but no space", self.version));
+            }
+            for (space, _) in &down.drop_indexes {
+                if !spaces.contains_key(space) {
+                    return Err(anyhow!("Down of migration {} refers to unknown space '{}'", self.version, space));
+                }
+            }
+            for (space, format) in &mut down.restore_format {
+                if !spaces.contains_key(space) {
+                    return Err(anyhow!("Down of migration {} refers to unknown space '{}'", self.version, space));
+                }
+                for (index, (name, field)) in format.iter_mut().enumerate() {
+                    field.name = name.clone();
+                    field.id = index + 1;
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    fn codegen_transform(&self, transform: &Option<String>) -> String {
+        match (&self.space, transform) {
+            (Some(space), Some(transform)) => {
+                let mut str = format!("Some(tarantool_schema::migrations::Transform {{\n");
+                str += &format!("                space: {}::SPACE_NAME.to_string(),\n", space);
+                str += &format!("                function: {},\n", transform);
+                str += &format!("                batch_size: {},\n", self.batch_size.unwrap_or(Migration::default_batch_size()));
+                str += &format!("            }})");
+                str
+            },
+            _ => format!("None"),
+        }
+    }
+
+    pub fn codegen(&self) -> Result<String, anyhow::Error> {
+        let mut str = format!("");
+        str += &format!("        tarantool_schema::migrations::Migration {{\n");
+        str += &format!("            version: {},\n", self.version);
+        str += &format!("            description: {:?}.to_string(),\n", self.description);
+        str += &format!("            checksum: {:?}.to_string(),\n", self.checksum()?);
+        str += &format!("            transform: {},\n", self.codegen_transform(&self.transform));
+        match &self.down {
+            Some(down) => {
+                str += &format!("            down: Some(tarantool_schema::migrations::Down {{\n");
+                str += &format!("                transform: {},\n", self.codegen_transform(&down.transform).replace("\n", "\n    "));
+                str += &format!("                drop_indexes: vec![\n");
+                for (space, indexes) in &down.drop_indexes {
+                    for index in indexes {
+                        str += &format!("                    ({}::SPACE_NAME.to_string(), {:?}.to_string()),\n", space, index);
+                    }
+                }
+                str += &format!("                ],\n");
+                str += &format!("                restore_format: vec![\n");
+                for (space, format) in &down.restore_format {
+                    str += &format!("                    ({}::SPACE_NAME.to_string(), vec![\n", space);
+                    for (name, field) in format {
+                        str += &format!("                        {},\n", field.runtime(&format!("{:?}.to_string()", name), "                        "));
+                    }
+                    str += &format!("                    ]),\n");
+                }
+                str += &format!("                ],\n");
+                str += &format!("            }}),\n");
+            },
+            None => {
+                str += &format!("            down: None,\n");
+            }
+        }
+        str += &format!("        }},\n");
+        Ok(str)
+    }
+}
@@ -1,8 +1,12 @@
 mod cargo;
-mod schema;
+mod collation;
+mod function;
+pub mod schema;
 mod space;
 mod field;
 mod index;
+mod migration;
+mod sequence;
 
 pub fn generate(schema_path: &std::path::Path, output_path: &std::path::Path, crate_name: Option<String>) -> Result<(), anyhow::Error> {
     let schema_yaml = {
@@ -32,3 +36,8 @@ pub fn generate(schema_path: &std::path::Path, output_path: &std::path::Path, cr
 
     Ok(())
 }
+
+// YAML schema of the spaces of the running instance
+pub fn introspect() -> Result<String, anyhow::Error> {
+    schema::Schema::introspect()?.to_yaml()
+}
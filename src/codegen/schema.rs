@@ -1,33 +1,132 @@
 use serde::{ Serialize, Deserialize};
 
+use super::collation::Collation;
+use super::function::Function;
 use super::space::Space;
+use super::migration::Migration;
 
 
 
+// Type names as tarantool stores them in system spaces, including the legacy ones
+pub(crate) fn parse_type<T: serde::de::DeserializeOwned>(name: &str) -> Result<T, anyhow::Error> {
+    let name = match name {
+        "num" => "unsigned",
+        "str" => "string",
+        "int" => "integer",
+        name => name,
+    };
+    serde_yaml::from_str(name).map_err(|err| anyhow::anyhow!("Unsupported type '{}': {}", name, err))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct Schema {
+pub struct Schema {
+    #[serde(skip_serializing_if = "Option::is_none")]
     tarantool: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tarantool_schema: Option<String>,
 
+    #[serde(default = "Schema::default_collations")]
+    #[serde(skip_serializing_if = "linked_hash_map::LinkedHashMap::is_empty")]
+    collations: linked_hash_map::LinkedHashMap<String, Collation>,
+
+    #[serde(default = "Schema::default_functions")]
+    #[serde(skip_serializing_if = "linked_hash_map::LinkedHashMap::is_empty")]
+    functions: linked_hash_map::LinkedHashMap<String, Function>,
+
     spaces: linked_hash_map::LinkedHashMap<String, Space>,
     dependencies: linked_hash_map::LinkedHashMap<String, String>,
+
+    #[serde(default = "Schema::default_migrations")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    migrations: Vec<Migration>,
 }
 
 impl Schema {
+    fn default_migrations() -> Vec<Migration> { vec![] }
+    fn default_collations() -> linked_hash_map::LinkedHashMap<String, Collation> { linked_hash_map::LinkedHashMap::new() }
+    fn default_functions() -> linked_hash_map::LinkedHashMap<String, Function> { linked_hash_map::LinkedHashMap::new() }
+
     pub fn new(schema_yaml: String) -> Result<Self, anyhow::Error> {
         let schema: Schema = serde_yaml::from_str(&schema_yaml)?;
         schema.validate()
     }
     fn validate(mut self) -> Result<Self, anyhow::Error> {
+        for (name, collation) in &mut self.collations {
+            if Collation::is_builtin(name) {
+                return Err(anyhow::anyhow!("Collation '{}' is built into tarantool and can't be declared", name));
+            }
+            collation.name = name.clone();
+        }
+
+        let mut functions = linked_hash_map::LinkedHashMap::new();
+        for (name, function) in self.functions.clone() {
+            functions.insert(name.clone(), function.validate(name)?);
+        }
+        self.functions = functions;
+
         let mut spaces =  linked_hash_map::LinkedHashMap::new();
         for (name, mut space) in self.spaces.clone() {
-            spaces.insert(name.clone(), space.validate(name)?);
+            spaces.insert(name.clone(), space.validate(name, &self.collations, &self.functions)?);
         }
         self.spaces = spaces;
 
+        let mut migrations = vec![];
+        for migration in self.migrations.clone() {
+            migrations.push(migration.validate(&self.spaces)?);
+        }
+        self.migrations = migrations;
+        for pair in self.migrations.windows(2) {
+            if pair[0].version >= pair[1].version {
+                return Err(anyhow::anyhow!("Migration versions must be increasing: {} goes before {}", pair[0].version, pair[1].version));
+            }
+        }
+
         Ok(self)
     }
 
+    // Describes the spaces of the running instance, the result serializes
+    // to the YAML accepted by `Schema::new`
+    pub fn introspect() -> Result<Self, anyhow::Error> {
+        let mut spaces = linked_hash_map::LinkedHashMap::new();
+        let mut collations = linked_hash_map::LinkedHashMap::new();
+        let mut functions = linked_hash_map::LinkedHashMap::new();
+        for live in crate::live::LiveSpace::all()? {
+            let used = live.format.iter().filter_map(|field| field.collation.clone())
+                .chain(live.indexes.iter().flat_map(|index| index.parts.iter().filter_map(|part| part.collation.clone())));
+            for name in used {
+                if Collation::is_builtin(&name) || collations.contains_key(&name) {
+                    continue;
+                }
+                if let Some(collation) = crate::live::LiveCollation::find(&name)? {
+                    collations.insert(name, Collation::from_live(&collation));
+                }
+            }
+            for index in &live.indexes {
+                if let Some(name) = &index.func {
+                    if !functions.contains_key(name) {
+                        let function = crate::live::LiveFunction::find_by_name(name)?
+                            .ok_or(anyhow::anyhow!("Can't find function '{}' of index '{}'", name, index.name))?;
+                        functions.insert(name.clone(), Function::from_live(&function)?);
+                    }
+                }
+            }
+            spaces.insert(live.name.clone(), Space::from_live(&live)?);
+        }
+        let schema = Schema {
+            tarantool: None,
+            tarantool_schema: None,
+            collations,
+            functions,
+            spaces,
+            dependencies: linked_hash_map::LinkedHashMap::new(),
+            migrations: vec![],
+        };
+        schema.validate()
+    }
+    pub fn to_yaml(&self) -> Result<String, anyhow::Error> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
     pub(crate) fn dependencies(&self) -> linked_hash_map::LinkedHashMap<String, String> {
         self.dependencies.clone()
     }
@@ -113,23 +212,85 @@ impl Schema {
 // }
 // "#;
 
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("pub fn collations() -> Vec<tarantool_schema::Collation> {{\n");
+        spaces_mod_rs += &format!("    vec![\n");
+        for (_, collation) in &self.collations {
+            spaces_mod_rs += &collation.codegen();
+        }
+        spaces_mod_rs += &format!("    ]\n");
+        spaces_mod_rs += &format!("}}\n");
+
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("pub fn functions() -> Vec<tarantool_schema::Function> {{\n");
+        spaces_mod_rs += &format!("    vec![\n");
+        for (_, function) in &self.functions {
+            spaces_mod_rs += &function.codegen();
+        }
+        spaces_mod_rs += &format!("    ]\n");
+        spaces_mod_rs += &format!("}}\n");
+
         spaces_mod_rs += &format!("\n");
         spaces_mod_rs += &format!("pub fn create() -> Result<(), anyhow::Error> {{\n");
-        for (space_name, code) in &spaces {
-            spaces_mod_rs += &format!("    {}::create()?;\n", space_name);
+        spaces_mod_rs += &format!("    // nothing is left half-built if any space, index or data row fails\n");
+        spaces_mod_rs += &format!("    tarantool_schema::transaction(|| {{\n");
+        spaces_mod_rs += &format!("        for collation in collations() {{ collation.ensure()?; }}\n");
+        spaces_mod_rs += &format!("        // functional indexes need their functions before they are built\n");
+        spaces_mod_rs += &format!("        for function in functions() {{ function.ensure()?; }}\n");
+        for (space_name, space) in &self.spaces {
+            if !space.is_vinyl() {
+                spaces_mod_rs += &format!("        {}::create()?;\n", space_name);
+            }
             // spaces_mod_rs += &format!("    if let Err(err) = {}::init() {{ log::error!(\"{}::init {{:?}}\", err); }}\n", space_name, space_name);
             // spaces_mod_rs += &format!("    if let Err(err) = {}::init_data() {{ log::error!(\"{}::init_data {{:?}}\", err); }}\n", space_name, space_name);
         }
         spaces_mod_rs += &format!("\n");
-        spaces_mod_rs += &format!("    init_data()?;\n");
+        for (space_name, space) in &self.spaces {
+            if !space.is_vinyl() {
+                spaces_mod_rs += &init_data_call(space_name, "        ");
+            }
+        }
+        spaces_mod_rs += &format!("        Ok(())\n");
+        spaces_mod_rs += &format!("    }})?;\n");
+        if self.spaces.iter().any(|(_, space)| space.is_vinyl()) {
+            spaces_mod_rs += &format!("    // a transaction can't mix memtx and vinyl: vinyl spaces are created after the commit,\n");
+            spaces_mod_rs += &format!("    // if one of them fails every space created above is dropped again\n");
+            spaces_mod_rs += &format!("    let vinyl = || -> Result<(), anyhow::Error> {{\n");
+            for (space_name, space) in &self.spaces {
+                if space.is_vinyl() {
+                    spaces_mod_rs += &format!("        {}::create()?;\n", space_name);
+                    spaces_mod_rs += &init_data_call(space_name, "        ");
+                }
+            }
+            spaces_mod_rs += &format!("        Ok(())\n");
+            spaces_mod_rs += &format!("    }};\n");
+            spaces_mod_rs += &format!("    if let Err(err) = vinyl() {{\n");
+            for (space_name, _) in self.spaces.iter().rev() {
+                spaces_mod_rs += &format!("        if tarantool::space::Space::find({}::SPACE_NAME).is_some() {{\n", space_name);
+                spaces_mod_rs += &format!("            if let Err(err) = {}::drop() {{ log::error!(\"Can't drop space '{{}}': {{}}\", {}::SPACE_NAME, err); }}\n", space_name, space_name);
+                spaces_mod_rs += &format!("        }}\n");
+            }
+            spaces_mod_rs += &format!("        return Err(err);\n");
+            spaces_mod_rs += &format!("    }}\n");
+        }
         spaces_mod_rs += &format!("    verify()?;\n");
         spaces_mod_rs += &format!("    Ok(())\n");
         spaces_mod_rs += &format!("}}\n");
 
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("pub fn ensure() -> Result<(), anyhow::Error> {{\n");
+        spaces_mod_rs += &format!("    for collation in collations() {{ collation.ensure()?; }}\n");
+        spaces_mod_rs += &format!("    for function in functions() {{ function.ensure()?; }}\n");
+        for (space_name, code) in &spaces {
+            spaces_mod_rs += &format!("    if {}::ensure()? {{ {}::init_data()?; }}\n", space_name, space_name);
+        }
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("    Ok(())\n");
+        spaces_mod_rs += &format!("}}\n");
         spaces_mod_rs += &format!("\n");
         spaces_mod_rs += &format!("pub fn init_data() -> Result<(), anyhow::Error> {{\n");
         for (space_name, code) in &spaces {
-            spaces_mod_rs += &format!("    {}::init_data()?;\n", space_name);
+            spaces_mod_rs += &init_data_call(space_name, "    ");
         }
         spaces_mod_rs += &format!("\n");
         spaces_mod_rs += &format!("    Ok(())\n");
@@ -144,6 +305,51 @@ impl Schema {
         spaces_mod_rs += &format!("    Ok(())\n");
         spaces_mod_rs += &format!("}}\n");
 
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("pub fn schema() -> Vec<tarantool_schema::Space> {{\n");
+        spaces_mod_rs += &format!("    vec![\n");
+        for (space_name, code) in &spaces {
+            spaces_mod_rs += &format!("        {}::schema(),\n", space_name);
+        }
+        spaces_mod_rs += &format!("    ]\n");
+        spaces_mod_rs += &format!("}}\n");
+
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("pub fn migrations() -> Vec<tarantool_schema::migrations::Migration> {{\n");
+        spaces_mod_rs += &format!("    vec![\n");
+        for migration in &self.migrations {
+            spaces_mod_rs += &migration.codegen()?;
+        }
+        spaces_mod_rs += &format!("    ]\n");
+        spaces_mod_rs += &format!("}}\n");
+
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("pub fn migrate() -> Result<(), anyhow::Error> {{\n");
+        spaces_mod_rs += &format!("    for collation in collations() {{ collation.ensure()?; }}\n");
+        spaces_mod_rs += &format!("    for function in functions() {{ function.ensure()?; }}\n");
+        spaces_mod_rs += &format!("    let created = tarantool_schema::migrations::migrate(&schema(), &migrations())?;\n");
+        for (space_name, code) in &spaces {
+            spaces_mod_rs += &format!("    if created.iter().any(|name| name == {}::SPACE_NAME) {{ {}::init_data()?; }}\n", space_name, space_name);
+        }
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("    verify()?;\n");
+        spaces_mod_rs += &format!("    Ok(())\n");
+        spaces_mod_rs += &format!("}}\n");
+
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("pub fn rollback_to(version: u64) -> Result<(), anyhow::Error> {{\n");
+        spaces_mod_rs += &format!("    tarantool_schema::migrations::rollback_to(&migrations(), version)\n");
+        spaces_mod_rs += &format!("}}\n");
+
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("pub fn alter() -> Result<(), anyhow::Error> {{\n");
+        for (space_name, code) in &spaces {
+            spaces_mod_rs += &format!("    {}::alter()?;\n", space_name);
+        }
+        spaces_mod_rs += &format!("\n");
+        spaces_mod_rs += &format!("    Ok(())\n");
+        spaces_mod_rs += &format!("}}\n");
+
         spaces_mod_rs += &format!("\n");
         spaces_mod_rs += &format!("pub fn drop() -> Result<(), anyhow::Error> {{\n");
         for (space_name, code) in &spaces {
@@ -158,4 +364,8 @@ impl Schema {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn init_data_call(space_name: &str, indent: &str) -> String {
+    format!("{}{}::init_data().map_err(|err| anyhow::anyhow!(\"Can't insert init data into space '{{}}': {{}}\", {}::SPACE_NAME, err))?;\n", indent, space_name, space_name)
+}
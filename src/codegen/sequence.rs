@@ -0,0 +1,48 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sequence {
+    // `<space>_seq` like the sequences tarantool generates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    // part of the primary index, its first part by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+
+    #[serde(default = "Sequence::default_cycle")]
+    pub cycle: bool,
+
+    #[serde(skip)]
+    pub field_index: u32,
+}
+impl Sequence {
+    fn default_cycle() -> bool { false }
+
+    pub(crate) fn from_live(live: &crate::live::LiveSequence, space_name: &str, format: &[super::field::Field]) -> Self {
+        let field_index = live.field_index.unwrap_or(1);
+        Sequence {
+            name: if live.name == format!("{}_seq", space_name) { None } else { Some(live.name.clone()) },
+            field: format.get(field_index as usize - 1).map(|field| field.name.clone()),
+            start: Some(live.start),
+            step: Some(live.step),
+            min: Some(live.min),
+            max: Some(live.max),
+            cycle: live.cycle,
+            field_index,
+        }
+    }
+
+    pub fn name(&self, space_name: &str) -> String {
+        self.name.clone().unwrap_or(format!("{}_seq", space_name))
+    }
+}
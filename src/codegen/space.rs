@@ -1,10 +1,15 @@
 use anyhow::anyhow;
+use convert_case::{Case, Casing};
 use serde::{Serialize, Deserialize};
 use tarantool::index::{IndexFieldType, IndexType};
-use tarantool::space::SpaceFieldType;
 
-use super::field::Field;
+use crate::FieldType;
+
+use super::collation::Collation;
+use super::field::{is_identifier, Field, RESERVED_TYPE_NAMES};
+use super::function::Function;
 use super::index::Index;
+use super::sequence::Sequence;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Space {
@@ -20,6 +25,21 @@ pub struct Space {
     #[serde(default = "Space::default_temporary")]
     temporary: bool,
 
+    #[serde(default = "Space::default_is_sync")]
+    is_sync: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u32>,
+
+    #[serde(default = "Space::default_defer_deletes")]
+    defer_deletes: bool,
+
+    #[serde(default = "Space::default_field_count")]
+    field_count: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+
     #[serde(default = "Space::default_format")]
     // format: Vec<Field>,
     format: linked_hash_map::LinkedHashMap<String, Field>,
@@ -27,29 +47,178 @@ pub struct Space {
     #[serde(default = "Space::default_indexes")]
     indexes: linked_hash_map::LinkedHashMap<String, Index>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sequence: Option<Sequence>,
+
     #[serde(default = "Space::default_init_data")]
-    init_data: Vec<linked_hash_map::LinkedHashMap<String, String>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    init_data: Vec<linked_hash_map::LinkedHashMap<String, serde_yaml::Value>>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     row_type: Option<String>,
 }
 impl Space {
     fn default_engine() -> tarantool::space::SpaceEngineType { tarantool::space::SpaceEngineType::Memtx }
     fn default_is_local() -> bool { false }
     fn default_temporary() -> bool { false }
+    fn default_is_sync() -> bool { false }
+    fn default_defer_deletes() -> bool { false }
+    fn default_field_count() -> u32 { 0 }
     fn default_format() -> linked_hash_map::LinkedHashMap<String, Field> { linked_hash_map::LinkedHashMap::new() }
     fn default_indexes() -> linked_hash_map::LinkedHashMap<String, Index> { linked_hash_map::LinkedHashMap::new() }
-    fn default_init_data() -> Vec<linked_hash_map::LinkedHashMap<String, String>> { vec![] }
+    fn default_init_data() -> Vec<linked_hash_map::LinkedHashMap<String, serde_yaml::Value>> { vec![] }
 }
 
 impl Space {
-    pub fn validate(mut self, name: String) -> Result<Self, anyhow::Error> {
+    pub(crate) fn from_live(live: &crate::live::LiveSpace) -> Result<Self, anyhow::Error> {
+        let mut format = vec![];
+        for field in &live.format {
+            format.push(Field::from_live(field)?);
+        }
+        // spaces created from Lua often have no format: name the indexed fields by position
+        for index in live.indexes.iter().filter(|index| index.func.is_none()) {
+            for part in &index.parts {
+                while format.len() < part.field_index as usize {
+                    let field_type = if format.len() + 1 == part.field_index as usize { part.field_type.as_str() } else { "any" };
+                    format.push(Field {
+                        id: 0,
+                        name: format!("field_{}", format.len() + 1),
+                        is_nullable: None,
+                        field_type: super::schema::parse_type(field_type)?,
+                        collation: None,
+                        rust_type: None,
+                        serde_with: None,
+                        rename: None,
+                        enum_values: vec![],
+                        items: None,
+                        fields: linked_hash_map::LinkedHashMap::new(),
+                    });
+                }
+            }
+        }
+
+        for index in live.indexes.iter().filter(|index| index.func.is_none()) {
+            for part in &index.parts {
+                if let (Some(path), Some(field)) = (&part.path, format.get_mut(part.field_index as usize - 1)) {
+                    field.declare_path(path, super::schema::parse_type(&part.field_type)?)?;
+                }
+            }
+        }
+
+        let mut indexes = linked_hash_map::LinkedHashMap::new();
+        for index in &live.indexes {
+            indexes.insert(index.name.clone(), Index::from_live(index, &format)?);
+        }
+        let sequence = live.sequence.as_ref().map(|sequence| Sequence::from_live(sequence, &live.name, &format));
+
+        Ok(Space {
+            name: live.name.clone(),
+            engine: super::schema::parse_type(&live.engine)?,
+            is_local: live.is_local,
+            temporary: live.temporary,
+            is_sync: live.is_sync,
+            defer_deletes: live.defer_deletes,
+            id: Some(live.id),
+            field_count: live.field_count,
+            user: if live.owner == "admin" { None } else { Some(live.owner.clone()) },
+            format: format.into_iter().map(|field| (field.name.clone(), field)).collect(),
+            indexes,
+            sequence,
+            init_data: vec![],
+            row_type: None,
+        })
+    }
+
+    pub fn validate(mut self, name: String, collations: &linked_hash_map::LinkedHashMap<String, Collation>,
+                    functions: &linked_hash_map::LinkedHashMap<String, Function>) -> Result<Self, anyhow::Error> {
         self.name = name;
         for (index, (name, field)) in self.format.iter_mut().enumerate() {
             field.name = name.clone();
             field.id = index + 1;
+            field.validate_nested(&format!("{}.{}", self.name, name))?;
+        }
+
+        if self.temporary && !matches!(self.engine, tarantool::space::SpaceEngineType::Memtx) {
+            return Err(anyhow!("Space '{}' is temporary but only memtx spaces can be temporary", self.name));
+        }
+        if self.is_sync && (self.is_local || self.temporary) {
+            return Err(anyhow!("Space '{}' is synchronous so it can't be local or temporary", self.name));
+        }
+        match self.engine {
+            tarantool::space::SpaceEngineType::Vinyl => {
+                for (index_name, index) in &self.indexes {
+                    if !matches!(index.index_type, IndexType::Tree) {
+                        return Err(anyhow!("Index '{}' from space '{}' is {:?} but vinyl supports only tree indexes", index_name, self.name, index.index_type));
+                    }
+                }
+            },
+            _ => {
+                if self.defer_deletes {
+                    return Err(anyhow!("Space '{}' has defer_deletes but it's a vinyl only option", self.name));
+                }
+                for (index_name, index) in &self.indexes {
+                    if index.has_vinyl_options() {
+                        return Err(anyhow!("Index '{}' from space '{}' has vinyl options but the space engine is {:?}", index_name, self.name, self.engine));
+                    }
+                }
+            },
+        }
+        if let Some(id) = self.id {
+            if id < 512 {
+                return Err(anyhow!("Space '{}' has id {} but ids below 512 are reserved for system spaces", self.name, id));
+            }
+        }
+        if self.field_count != 0 && (self.field_count as usize) < self.format.len() {
+            return Err(anyhow!("Space '{}' has field_count {} but {} fields in format", self.name, self.field_count, self.format.len()));
         }
 
+        for (name, field) in &self.format {
+            if let Some(collation) = &field.collation {
+                match field.field_type {
+                    FieldType::String | FieldType::Scalar => {},
+                    _ => return Err(anyhow!("Field '{}' from space '{}' has collation '{}' but its type '{}' isn't string or scalar", name, self.name, collation, field.field_type)),
+                }
+                if !Collation::is_builtin(collation) && !collations.contains_key(collation) {
+                    return Err(anyhow!("Field '{}' from space '{}' has unknown collation '{}'", name, self.name, collation));
+                }
+            }
+        }
         for (index_name, index) in &self.indexes {
+            for part in &index.parts {
+                if let Some(collation) = &part.collation {
+                    match part.index_field_type {
+                        IndexFieldType::String | IndexFieldType::Scalar => {},
+                        _ => return Err(anyhow!("Index part '{}' of index '{}' from space '{}' has collation '{}' but its type '{:?}' isn't string or scalar", part.field_name, index_name, self.name, collation, part.index_field_type)),
+                    }
+                    if !Collation::is_builtin(collation) && !collations.contains_key(collation) {
+                        return Err(anyhow!("Index part '{}' of index '{}' from space '{}' has unknown collation '{}'", part.field_name, index_name, self.name, collation));
+                    }
+                }
+            }
+        }
+
+        for (index_position, (index_name, index)) in self.indexes.iter().enumerate() {
+            let func = match &index.func {
+                Some(func) => func,
+                None => continue,
+            };
+            let function = functions.get(func)
+                .ok_or(anyhow!("Index '{}' from space '{}' refers to unknown function '{}'", index_name, self.name, func))?;
+            if index_position == 0 {
+                return Err(anyhow!("Index '{}' from space '{}' is functional so it can't be the primary index", index_name, self.name));
+            }
+            if !matches!(self.engine, tarantool::space::SpaceEngineType::Memtx) || !matches!(index.index_type, IndexType::Tree) {
+                return Err(anyhow!("Functional index '{}' from space '{}' must be a memtx tree index", index_name, self.name));
+            }
+            if !function.is_deterministic {
+                return Err(anyhow!("Function '{}' of index '{}' from space '{}' must be is_deterministic", func, index_name, self.name));
+            }
+            if function.body.is_some() && !function.is_sandboxed {
+                return Err(anyhow!("Lua function '{}' of index '{}' from space '{}' must be is_sandboxed", func, index_name, self.name));
+            }
+        }
+
+        for (index_name, index) in self.indexes.iter().filter(|(_, index)| index.func.is_none()) {
             for part in &index.parts {
                 if let None = self.filed_by_name(&part.field_name) {
                     return Err(anyhow!("Can't find field by name '{}' in index '{}' from space '{}'", part.field_name, index_name, self.name))
@@ -57,7 +226,139 @@ impl Space {
             }
         }
 
-        Ok(self.validate_indexes()?)
+        let mut row_names: Vec<String> = vec![];
+        for (name, field) in &self.format {
+            let row_name = field.name();
+            if !is_identifier(&row_name) {
+                return Err(anyhow!("Field '{}' from space '{}' has Row name '{}' which isn't a Rust identifier, set rename", name, self.name, row_name));
+            }
+            if row_names.contains(&row_name) {
+                return Err(anyhow!("Field '{}' from space '{}' has Row name '{}' which is already taken", name, self.name, row_name));
+            }
+            row_names.push(row_name);
+            if field.rust_type.as_ref().map(|rust_type| rust_type.trim().is_empty()).unwrap_or(false) {
+                return Err(anyhow!("Field '{}' from space '{}' has empty rust_type", name, self.name));
+            }
+        }
+
+        // generated types share the space module with the Row and the imported ones
+        let mut type_names = vec![];
+        for (_, field) in self.format.iter().filter(|(_, field)| !self.is_rtree_field(&field.name)) {
+            field.type_names(&Field::struct_name("", &field.name()), &mut type_names);
+        }
+        for (_, index) in self.indexes.iter().filter(|(_, index)| matches!(index.index_type, IndexType::Rtree)) {
+            type_names.push(format!("{}Point", index.name().to_case(Case::Pascal)));
+            type_names.push(format!("{}Rect", index.name().to_case(Case::Pascal)));
+        }
+        for (position, type_name) in type_names.iter().enumerate() {
+            if RESERVED_TYPE_NAMES.contains(&type_name.as_str()) || type_names[..position].contains(type_name) {
+                return Err(anyhow!("Space '{}' generates type '{}' twice or over a type of the space module, rename the field", self.name, type_name));
+            }
+        }
+
+        for (index, row) in self.init_data.iter().enumerate() {
+            for (name, field) in self.format.iter().filter(|(_, field)| !field.enum_values.is_empty()) {
+                if let Some(value) = row.get(name).filter(|value| !value.is_null()) {
+                    if !value.as_str().map(|value| field.enum_values.iter().any(|name| name == value)).unwrap_or(false) {
+                        return Err(anyhow!("Row #{} of init_data of space '{}' has {:?} in field '{}' which isn't one of {:?}", index, self.name, value, name, field.enum_values));
+                    }
+                }
+            }
+        }
+
+        self.validate_index_types()?;
+        Ok(self.validate_indexes()?.validate_sequence()?)
+    }
+
+    // Tarantool's per-type index constraints; every violation is reported at once
+    fn validate_index_types(&self) -> Result<(), anyhow::Error> {
+        let mut errors = vec![];
+        for (index_position, (index_name, index)) in self.indexes.iter().enumerate() {
+            let mut error = |message: String| errors.push(format!("Index '{}' from space '{}' {}", index_name, self.name, message));
+            // a part without is_nullable takes it from the field
+            let nullable = index.parts.iter()
+                .any(|part| part.is_nullable == Some(true) || (part.is_nullable.is_none() && part.path.is_none() && self.format.get(&part.field_name).map(|field| field.is_nullable == Some(true)).unwrap_or(false)));
+            if index_position == 0 {
+                if !index.unique {
+                    error(format!("is primary so it must be unique"));
+                }
+                if nullable {
+                    error(format!("is primary so its parts can't be nullable"));
+                }
+            } else if index_name == "primary" {
+                error(format!("is named 'primary' but the primary index must be declared first"));
+            }
+            if !matches!(index.index_type, IndexType::Rtree) && (index.dimension.is_some() || index.distance.is_some()) {
+                error(format!("has dimension or distance but only rtree indexes have them"));
+            }
+            match index.index_type {
+                IndexType::Tree => {},
+                IndexType::Hash => {
+                    if !index.unique {
+                        error(format!("is hash so it must be unique"));
+                    }
+                    if nullable {
+                        error(format!("is hash so its parts can't be nullable"));
+                    }
+                },
+                IndexType::Rtree => {
+                    if !(1..=20).contains(&index.dimension()) {
+                        error(format!("has dimension {} but rtree supports 1 to 20", index.dimension()));
+                    }
+                    if index.unique {
+                        error(format!("is rtree so it can't be unique"));
+                    }
+                    if index.parts.len() != 1 || !matches!(index.parts[0].index_field_type, IndexFieldType::Array) {
+                        error(format!("is rtree so it must have exactly one array part"));
+                    }
+                },
+                IndexType::Bitset => {
+                    if index.unique {
+                        error(format!("is bitset so it can't be unique"));
+                    }
+                    if index.parts.len() != 1 || !matches!(index.parts[0].index_field_type, IndexFieldType::Unsigned | IndexFieldType::String) {
+                        error(format!("is bitset so it must have exactly one unsigned or string part"));
+                    }
+                },
+            }
+        }
+        if !errors.is_empty() {
+            return Err(anyhow!("{}", errors.join("\n")));
+        }
+        Ok(())
+    }
+
+    fn validate_sequence(mut self) -> Result<Self, anyhow::Error> {
+        let primary = self.indexes.iter().next().map(|(_, index)| index.clone());
+        let sequence = match &mut self.sequence {
+            Some(sequence) => sequence,
+            None => return Ok(self),
+        };
+        let primary = match primary {
+            Some(primary) => primary,
+            None => return Err(anyhow!("Sequence of space '{}' requires a primary index", self.name)),
+        };
+        let part = match &sequence.field {
+            Some(field) => primary.parts.iter().find(|part| &part.field_name == field)
+                .ok_or(anyhow!("Sequence field '{}' of space '{}' isn't a part of primary index '{}'", field, self.name, primary.name))?,
+            None => primary.parts.first()
+                .ok_or(anyhow!("Primary index '{}' of space '{}' has no parts", primary.name, self.name))?,
+        };
+        match part.index_field_type {
+            IndexFieldType::Unsigned | IndexFieldType::Integer => {},
+            _ => return Err(anyhow!("Sequence field '{}' of space '{}' must be unsigned or integer but it's '{:?}'", part.field_name, self.name, part.index_field_type)),
+        }
+        sequence.field = Some(part.field_name.clone());
+        sequence.field_index = part.part.as_ref().map(|part| part.field_index).unwrap_or(0);
+        Ok(self)
+    }
+
+    pub(crate) fn is_vinyl(&self) -> bool {
+        matches!(self.engine, tarantool::space::SpaceEngineType::Vinyl)
+    }
+
+    fn is_rtree_field(&self, name: &str) -> bool {
+        self.indexes.iter().any(|(_, index)| matches!(index.index_type, IndexType::Rtree) && index.parts.iter().any(|part| part.field_name == name))
     }
 
     fn filed_by_name(&self, name: &str) -> Option<Field> {
@@ -73,36 +374,78 @@ impl Space {
         for (index_name, index) in &mut self.indexes {
             index.name = index_name.clone();
             for (part_index, part) in index.parts.iter_mut().enumerate() {
+                if index.func.is_some() {
+                    part.part = Some(tarantool::index::IndexPart {
+                        field_index: part_index as u32 + 1,
+                        field_type: part.index_field_type.clone(),
+                        collation: part.collation.clone(),
+                        is_nullable: part.is_nullable,
+                        path: part.path.clone(),
+                    });
+                    continue;
+                }
                 let field = match this.filed_by_name(&part.field_name) {
                     Some(field) => field,
                     None => return Err(anyhow!("Can't find field by name '{}' in index '{}' from space '{}'", part.field_name, index_name, self.name))
                 };
-                let type_incorrect = match part.index_field_type {
-                    IndexFieldType::Unsigned    => { field.field_type != SpaceFieldType::Unsigned }
-                    IndexFieldType::String      => { field.field_type != SpaceFieldType::String }
-                    IndexFieldType::Integer     => { field.field_type != SpaceFieldType::Integer }
-                    IndexFieldType::Number      => { field.field_type != SpaceFieldType::Number }
-                    IndexFieldType::Double      => { field.field_type != SpaceFieldType::Double }
-                    IndexFieldType::Decimal     => { field.field_type != SpaceFieldType::Decimal }
-                    IndexFieldType::Boolean     => { field.field_type != SpaceFieldType::Boolean }
-                    IndexFieldType::Varbinary   => { field.field_type != SpaceFieldType::Integer }
-                    IndexFieldType::Uuid        => { field.field_type != SpaceFieldType::Uuid }
-                    IndexFieldType::Array       => { field.field_type != SpaceFieldType::Array }
-                    IndexFieldType::Scalar      => { field.field_type != SpaceFieldType::Scalar }
+                // with a path the part indexes a value nested in a map or array field
+                let leaf = match &part.path {
+                    Some(path) => field.path_field(path)
+                        .map_err(|err| anyhow!("Index part '{}' of index '{}' from space '{}' has incorrect path '{}': {}", part.field_name, index_name, self.name, path, err))?
+                        .clone(),
+                    None => field.clone(),
                 };
-                if type_incorrect {
-                    return Err(anyhow!("Index part '{}' of index '{}' from space '{}' has incorrect type. Index part type is '{:?}' but field type is '{:?}'", part.field_name, index_name, self.name, part.index_field_type, field.field_type))
+                let part_type = crate::diff::type_name(&part.index_field_type);
+                if !super::index::compatible(&leaf.field_type.to_string(), &part_type) {
+                    return Err(anyhow!("Index part '{}' of index '{}' from space '{}' has type '{}' which isn't compatible with field type '{}': one of them must contain the other (any > scalar > number > integer > unsigned)",
+                                       part.field_name, index_name, self.name, part_type, leaf.field_type))
                 }
+                // like the collation, nullability comes from the field unless the part sets it,
+                // so the created index, the runtime part and the diff agree
+                part.is_nullable = part.is_nullable.or(leaf.is_nullable);
                 part.part = Some(tarantool::index::IndexPart {
                     field_index: field.id as u32,
                     field_type: part.index_field_type.clone(),
-                    collation: None,
+                    // string parts inherit the collation of the field
+                    collation: match part.index_field_type {
+                        IndexFieldType::String | IndexFieldType::Scalar => part.collation.clone().or(leaf.collation.clone()),
+                        _ => part.collation.clone(),
+                    },
                     is_nullable: part.is_nullable,
                     path: part.path.clone(),
                 });
                 part.field = Some(field);
             }
         }
+        self.validate_multikey()
+    }
+
+    // `[*]` in a part path makes a multikey index: a tuple is indexed once per array element
+    fn validate_multikey(self) -> Result<Self, anyhow::Error> {
+        for (index_position, (index_name, index)) in self.indexes.iter().enumerate() {
+            let multikey: Vec<(&String, &String)> = index.parts.iter()
+                .filter_map(|part| part.path.as_ref().filter(|path| path.contains("[*]")).map(|path| (&part.field_name, path)))
+                .collect();
+            let (first_field, first_path) = match multikey.first() {
+                Some(first) => *first,
+                None => continue,
+            };
+            if index_position == 0 {
+                return Err(anyhow!("Index '{}' from space '{}' is multikey so it can't be primary: a primary key must be unique per tuple", index_name, self.name));
+            }
+            if index.func.is_some() {
+                return Err(anyhow!("Functional index '{}' from space '{}' can't have multikey paths, use an is_multikey function instead", index_name, self.name));
+            }
+            if !matches!(index.index_type, IndexType::Tree) {
+                return Err(anyhow!("Index '{}' from space '{}' is multikey but only tree indexes can be multikey", index_name, self.name));
+            }
+            let prefix = &first_path[..first_path.find("[*]").unwrap()];
+            for (field, path) in &multikey {
+                if *field != first_field || &path[..path.find("[*]").unwrap()] != prefix || path.matches("[*]").count() > 1 {
+                    return Err(anyhow!("Multikey parts of index '{}' from space '{}' must expand the same array: '{}{}' and '{}{}'", index_name, self.name, first_field, first_path, field, path));
+                }
+            }
+        }
         Ok(self)
     }
 
@@ -113,25 +456,12 @@ impl Space {
         for (index, row) in self.init_data.iter().enumerate() {
             str += &format!("    space()?.insert(&Row{{");
             for (_, field) in &self.format {
-                let val = match row.get(&field.name) {
+                let val = match row.get(&field.name).filter(|val| !val.is_null()) {
                     Some(val) => {
-                        Some(match field.field_type {
-                            SpaceFieldType::String => { format!("\"{}\".to_string()", val) }
-                            SpaceFieldType::Uuid => { format!("\"{}\".to_string()", val) }
-
-                            SpaceFieldType::Unsigned => { format!("{}", val) }
-                            SpaceFieldType::Number => { format!("{}", val) }
-                            SpaceFieldType::Double => { format!("{}", val) }
-                            SpaceFieldType::Integer => { format!("{}", val) }
-                            SpaceFieldType::Boolean => { format!("{}", val) }
-                            SpaceFieldType::Decimal => { format!("{}", val) }
-                            space_field_type => {
-                                return Err(anyhow!("Incorrect data value in row #{} of space '{}'. Filed '{}' type '{}' doesn't support", index, self.name, field.name, space_field_type));
-                            }
-                            // SpaceFieldType::Any => {}
-                            // SpaceFieldType::Array => {}
-                            // SpaceFieldType::Scalar => {}
-                        })
+                        Some(seed_literal(field, &Field::struct_name("", &field.name()), val, self.is_rtree_field(&field.name))
+                            .map_err(|err| anyhow!("Incorrect data value in row #{} of space '{}'. Field '{}': {}", index, self.name, field.name, err))?)
+                            // a domain type is built from the generated one
+                            .map(|val| if field.rust_type.is_some() { format!("({}).into()", val) } else { val })
                     },
                     None => {
                         match field.is_nullable {
@@ -203,7 +533,7 @@ use tarantool::tuple::{ AsTuple, Tuple };
 
         str += &format!("\n");
         for (_, field) in &self.format {
-            str += &format!("const {}: &str = \"{}\";\n", field.const_name(), field.name());
+            str += &format!("const {}: &str = \"{}\";\n", field.const_name(), field.name);
             // const FIELD_USER_ID: Field = Field { id: 1, name: "user_id", type_: SpaceFieldType::String, index_name: Some(FIELD_USER_ID__INDEX) };
         }
 
@@ -247,22 +577,42 @@ use tarantool::tuple::{ AsTuple, Tuple };
             str += &format!("pub fn {}_index() -> Result<tarantool::index::Index, anyhow::Error> {{ space()?.index({}).ok_or(anyhow::anyhow!(\"Can't find space '{{}}' index '{{}}'\", SPACE_NAME, {})) }}\n", index.name(), index.const_name(), index.const_name());
         }
 
+        for (_, index) in self.indexes.iter().filter(|(_, index)| matches!(index.index_type, IndexType::Rtree)) {
+            str += &format!("\n");
+            str += &format!("pub type {}Point = tarantool_schema::geo::Point<{}>;\n", index.name().to_case(Case::Pascal), index.dimension());
+            str += &format!("pub type {}Rect = tarantool_schema::geo::Rect<{}>;\n", index.name().to_case(Case::Pascal), index.dimension());
+            str += &format!("// rows whose point or rectangle lies within `rect`\n");
+            str += &format!("pub fn {}_select_within(rect: &{}Rect) -> Result<Vec<Row>, anyhow::Error> {{\n", index.name(), index.name().to_case(Case::Pascal));
+            str += &format!("    let mut rows = vec![];\n");
+            str += &format!("    for tuple in {}_index()?.select(IteratorType::LE, &(rect,))? {{ rows.push(tuple.as_struct::<Row>()?); }}\n", index.name());
+            str += &format!("    Ok(rows)\n");
+            str += &format!("}}\n");
+            str += &format!("// up to `limit` rows nearest to `point` by the index distance\n");
+            str += &format!("pub fn {}_select_neighbors(point: &{}Point, limit: usize) -> Result<Vec<Row>, anyhow::Error> {{\n", index.name(), index.name().to_case(Case::Pascal));
+            str += &format!("    let mut rows = vec![];\n");
+            str += &format!("    for tuple in {}_index()?.select(IteratorType::Neighbor, &(point,))?.take(limit) {{ rows.push(tuple.as_struct::<Row>()?); }}\n", index.name());
+            str += &format!("    Ok(rows)\n");
+            str += &format!("}}\n");
+        }
+
         str += &format!("\n");
+        str += &format!("pub fn schema() -> tarantool_schema::Space {{ SPACE.read().clone() }}\n");
         str += &format!("pub fn create() -> Result<(), anyhow::Error> {{ SPACE.read().create() }}\n");
+        str += &format!("pub fn ensure() -> Result<bool, anyhow::Error> {{ SPACE.read().ensure() }}\n");
         str += &format!("pub fn verify() -> Result<(), anyhow::Error> {{ SPACE.read().verify() }}\n");
+        str += &format!("pub fn alter() -> Result<Vec<tarantool_schema::diff::Change>, anyhow::Error> {{ SPACE.read().alter() }}\n");
         str += &format!("pub fn drop() -> Result<(), anyhow::Error> {{ tarantool_schema::Space::drop(&mut SPACE.read()) }}\n");
         str += &format!("pub fn truncate() -> Result<(), anyhow::Error> {{ SPACE.read().truncate() }}\n");
+        if self.sequence.is_some() {
+            str += &format!("pub fn insert_auto(row: &Row) -> Result<u64, anyhow::Error> {{ SPACE.read().insert_auto(row) }}\n");
+        }
         str += &format!("\n");
         str += &format!("static SPACE: once_cell::sync::Lazy<parking_lot::RwLock<tarantool_schema::Space>> = once_cell::sync::Lazy::new(|| {{\n");
 
         str += &format!("\n");
         str += &format!("    let mut format = vec![];\n");
         for (_, field) in &self.format {
-            str += &format!("    format.push(tarantool_schema::Field {{\n");
-            str += &format!("        name: {}.to_string(),\n", field.const_name());
-            str += &format!("        field_type: SpaceFieldType::{},\n", field.field_type);
-            str += &format!("        is_nullable: {:?},\n", field.is_nullable);
-            str += &format!("    }});\n");
+            str += &format!("    format.push({});\n", field.runtime(&format!("{}.to_string()", field.const_name()), "    "));
         }
 
         str += &format!("\n");
@@ -272,6 +622,14 @@ use tarantool::tuple::{ AsTuple, Tuple };
             str += &format!("        name: {}.to_string(),\n", index.const_name());
             str += &format!("        unique: {},\n", index.unique);
             str += &format!("        index_type: IndexType::{:?},\n", index.index_type);
+            str += &format!("        bloom_fpr: {:?},\n", index.bloom_fpr);
+            str += &format!("        page_size: {:?},\n", index.page_size);
+            str += &format!("        range_size: {:?},\n", index.range_size);
+            str += &format!("        run_count_per_level: {:?},\n", index.run_count_per_level);
+            str += &format!("        run_size_ratio: {:?},\n", index.run_size_ratio);
+            str += &format!("        func: {},\n", index.func.as_ref().map(|func| format!("Some({:?}.to_string())", func)).unwrap_or("None".to_string()));
+            str += &format!("        dimension: {:?},\n", index.dimension);
+            str += &format!("        distance: {},\n", index.distance.as_ref().map(|distance| format!("Some(tarantool::index::RtreeIndexDistanceType::{:?})", distance)).unwrap_or("None".to_string()));
             str += &format!("        parts: {{\n");
             str += &format!("            let mut parts = vec![];\n");
             for part in &index.parts {
@@ -283,7 +641,7 @@ use tarantool::tuple::{ AsTuple, Tuple };
                 let part = part.part.clone().unwrap();
                 str += &format!("                    field_index: {},\n", part.field_index);
                 str += &format!("                    field_type: IndexFieldType::{:?},\n", part.field_type);
-                str += &format!("                    collation: {:?},\n", part.collation);
+                str += &format!("                    collation: {},\n", part.collation.map(|collation| format!("Some({:?}.to_string())", collation)).unwrap_or("None".to_string()));
                 str += &format!("                    is_nullable: {:?},\n", part.is_nullable);
                 str += &format!("                    path: {},\n", part.path.map(|path| format!("Some(\"{}\".to_string())", path)).unwrap_or("None".to_string()));
                 str += &format!("                }},\n");
@@ -301,11 +659,32 @@ use tarantool::tuple::{ AsTuple, Tuple };
         str += &format!("\n");
         str += &format!("    let space = tarantool_schema::Space {{\n");
         str += &format!("        name: SPACE_NAME.to_string(),\n");
-        str += &format!("        engine: tarantool::space::SpaceEngineType::Memtx,\n");
-        str += &format!("        is_local: false,\n");
-        str += &format!("        temporary: false,\n");
+        str += &format!("        engine: tarantool::space::SpaceEngineType::{:?},\n", self.engine);
+        str += &format!("        is_local: {},\n", self.is_local);
+        str += &format!("        temporary: {},\n", self.temporary);
+        str += &format!("        is_sync: {},\n", self.is_sync);
+        str += &format!("        defer_deletes: {},\n", self.defer_deletes);
+        str += &format!("        id: {:?},\n", self.id);
+        str += &format!("        field_count: {},\n", self.field_count);
+        str += &format!("        user: {},\n", self.user.as_ref().map(|user| format!("Some({:?}.to_string())", user)).unwrap_or("None".to_string()));
         str += &format!("        format,\n");
         str += &format!("        indexes,\n");
+        match &self.sequence {
+            Some(sequence) => {
+                str += &format!("        sequence: Some(tarantool_schema::Sequence {{\n");
+                str += &format!("            name: {:?}.to_string(),\n", sequence.name(&self.name));
+                str += &format!("            field_index: {},\n", sequence.field_index);
+                str += &format!("            start: {:?},\n", sequence.start);
+                str += &format!("            step: {:?},\n", sequence.step);
+                str += &format!("            min: {:?},\n", sequence.min);
+                str += &format!("            max: {:?},\n", sequence.max);
+                str += &format!("            cycle: {},\n", sequence.cycle);
+                str += &format!("        }}),\n");
+            },
+            None => {
+                str += &format!("        sequence: None,\n");
+            }
+        }
         str += &format!("    }};\n");
         // str += &format!("    space.create().unwrap();\n");
         str += &format!("    parking_lot::RwLock::new(space)\n");
@@ -316,31 +695,38 @@ use tarantool::tuple::{ AsTuple, Tuple };
 
         str += &format!("\n");
         str += &format!("#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\n");
+        let mut structs = format!("");
         str += &format!("pub struct Row {{\n");
         for (_, field) in &self.format {
             let field_type = match field.field_type {
-                SpaceFieldType::Unsigned => { "u64" }
-                SpaceFieldType::String => { "String" }
-                SpaceFieldType::Number => { "u64" }
-                SpaceFieldType::Double => { "f64" }
-                SpaceFieldType::Integer => { "i64" }
-                SpaceFieldType::Boolean => { "bool" }
-                SpaceFieldType::Decimal => { "f64" }
-                SpaceFieldType::Uuid => { "String" }
-                space_field_type => { return Err(anyhow!("Type '{}' is not supported", space_field_type)); }
-                // SpaceFieldType::Scalar => {}
-                // SpaceFieldType::Array => {  }
-                // SpaceFieldType::Any => {}
+                _ if field.rust_type.is_some() => {
+                    // the override is built `From` the generated type, enums and structs are still declared
+                    if !self.is_rtree_field(&field.name) {
+                        field.base_rust_type(&Field::struct_name("", &field.name()), &mut structs)?;
+                    }
+                    field.rust_type.clone().unwrap()
+                }
+                FieldType::Varbinary => { format!("Vec<u8>") }
+                // a point or a rectangle of an rtree index
+                FieldType::Array if self.is_rtree_field(&field.name) => { format!("Vec<f64>") }
+                _ => field.base_rust_type(&Field::struct_name("", &field.name()), &mut structs)?,
             };
             let field_type = match field.is_nullable {
                 Some(true) => format!("Option<{}>", field_type),
                 Some(false) => format!("{}", field_type),
                 None => format!("{}", field_type),
             };
+            if let Some(serde_with) = &field.serde_with {
+                str += &format!("    #[serde(with = {:?})]\n", serde_with);
+            } else if field.field_type == FieldType::Varbinary && field.rust_type.is_none() {
+                // a MessagePack bin, not an array of numbers
+                str += &format!("    #[serde(with = \"serde_bytes\")]\n");
+            }
             str += &format!("    pub {}: {},\n", field.name(), field_type);
         }
         str += &format!("}}\n");
         str += &format!("impl tarantool::tuple::AsTuple for Row {{}}\n");
+        str += &structs;
         if let Some(row_type) = &self.row_type {
             str += &format!("impl Row {{\n");
             str += &format!("    pub fn __check() -> Row {{\n");
@@ -349,7 +735,7 @@ use tarantool::tuple::{ AsTuple, Tuple };
             str += &format!("        {}::__info__fields_count__{}();\n", row_type, self.format.len());
             str += &format!("        Row {{\n");
             for (index, (_, field)) in self.format.iter().enumerate() {
-                str += &format!("            {}: {}::__info__field_{}__{}(),\n", field.name, row_type, index, field.name);
+                str += &format!("            {}: {}::__info__field_{}__{}(),\n", field.name(), row_type, index, field.name);
             }
             str += &format!("        }}\n");
             str += &format!("    }}\n");
@@ -367,4 +753,290 @@ use tarantool::tuple::{ AsTuple, Tuple };
         // });
         Ok(str)
     }
-}
\ No newline at end of file
+}
+
+// Rust expression of a seed value from `init_data`
+fn seed_literal(field: &Field, struct_name: &str, value: &serde_yaml::Value, rtree: bool) -> Result<String, anyhow::Error> {
+    let field_type = field.field_type;
+    let scalar = || -> Result<String, anyhow::Error> {
+        match value {
+            serde_yaml::Value::String(value) => Ok(value.clone()),
+            serde_yaml::Value::Number(value) => Ok(format!("{}", value)),
+            serde_yaml::Value::Bool(value) => Ok(format!("{}", value)),
+            value => Err(anyhow!("expected a {} value but got {:?}", field_type, value)),
+        }
+    };
+    Ok(match field_type {
+        FieldType::String | FieldType::Unsigned if !field.enum_values.is_empty() => {
+            let value = scalar()?;
+            field.enum_literal(struct_name, &value).ok_or(anyhow!("'{}' isn't one of {:?}", value, field.enum_values))?
+        },
+        FieldType::String => format!("{:?}.to_string()", scalar()?),
+        // parsed here so a malformed seed fails the build, not the node
+        FieldType::Uuid => {
+            let value = scalar()?;
+            let bytes = parse_uuid(&value).ok_or(anyhow!("'{}' isn't a UUID", value))?;
+            let bytes: Vec<String> = bytes.iter().map(|byte| format!("0x{:02x}", byte)).collect();
+            format!("tarantool::uuid::Uuid::from_bytes([{}])", bytes.join(", "))
+        },
+        FieldType::Unsigned | FieldType::Integer | FieldType::Boolean => scalar()?,
+        FieldType::Number => match value {
+            serde_yaml::Value::Number(number) => match (number.as_i64(), number.as_u64(), number.as_f64()) {
+                (Some(value), _, _) => format!("tarantool_schema::types::Number::Integer({})", value),
+                (None, Some(value), _) => format!("tarantool_schema::types::Number::Unsigned({})", value),
+                (None, None, Some(value)) if value.is_finite() => format!("tarantool_schema::types::Number::Double({:?})", value),
+                _ => return Err(anyhow!("unsupported number {:?}", number)),
+            },
+            value => return Err(anyhow!("expected a number but got {:?}", value)),
+        },
+        // quote decimals in YAML to keep every digit: "12.30"
+        FieldType::Decimal => {
+            let value = scalar()?;
+            if !is_decimal(&value) {
+                return Err(anyhow!("'{}' isn't a decimal number", value));
+            }
+            format!("{:?}.parse::<tarantool::decimal::Decimal>().map_err(|_| anyhow::anyhow!(\"Incorrect decimal '{}'\"))?", value, value)
+        },
+        FieldType::Double => {
+            let value = scalar()?;
+            let double = value.parse::<f64>().ok().filter(|double| double.is_finite()).ok_or(anyhow!("'{}' isn't a double", value))?;
+            format!("{:?}f64", double)
+        },
+        FieldType::Varbinary => match value {
+            serde_yaml::Value::String(value) => format!("{:?}.as_bytes().to_vec()", value),
+            serde_yaml::Value::Sequence(bytes) => {
+                let mut items = vec![];
+                for byte in bytes {
+                    items.push(format!("{}u8", byte.as_u64().filter(|byte| *byte <= 255).ok_or(anyhow!("varbinary items must be bytes, got {:?}", byte))?));
+                }
+                format!("vec![{}]", items.join(", "))
+            },
+            value => return Err(anyhow!("varbinary is a string or a list of bytes, got {:?}", value)),
+        },
+        FieldType::Array if rtree => match value {
+            serde_yaml::Value::Sequence(coords) => {
+                let mut items = vec![];
+                for coord in coords {
+                    items.push(format!("{:?}f64", coord.as_f64().ok_or(anyhow!("coordinates must be numbers, got {:?}", coord))?));
+                }
+                format!("vec![{}]", items.join(", "))
+            },
+            value => return Err(anyhow!("expected a list of coordinates but got {:?}", value)),
+        },
+        FieldType::Array => match value {
+            serde_yaml::Value::Sequence(items) => {
+                let mut literals = vec![];
+                for (position, item) in items.iter().enumerate() {
+                    literals.push(match &field.items {
+                        Some(declared) => nested_literal(declared, &format!("{}Item", struct_name), item)
+                            .map_err(|err| anyhow!("item #{}: {}", position + 1, err))?,
+                        None => rmpv_literal(item)?,
+                    });
+                }
+                format!("vec![{}]", literals.join(", "))
+            },
+            value => return Err(anyhow!("expected a list but got {:?}", value)),
+        },
+        FieldType::Map if !field.fields.is_empty() => match value {
+            serde_yaml::Value::Mapping(map) => {
+                for (key, _) in map {
+                    if !key.as_str().map(|key| field.fields.contains_key(key)).unwrap_or(false) {
+                        return Err(anyhow!("key {:?} isn't declared in fields", key));
+                    }
+                }
+                let mut entries = vec![];
+                for (name, declared) in &field.fields {
+                    let literal = match map.get(&serde_yaml::Value::String(name.clone())).filter(|value| !value.is_null()) {
+                        Some(value) => nested_literal(declared, &Field::struct_name(struct_name, name), value)
+                            .map_err(|err| anyhow!("field '{}': {}", name, err))?,
+                        None if declared.is_nullable == Some(true) => format!("None"),
+                        None => return Err(anyhow!("field '{}' is missing", name)),
+                    };
+                    entries.push(format!("{}: {}", name, literal));
+                }
+                format!("{} {{ {} }}", struct_name, entries.join(", "))
+            },
+            value => return Err(anyhow!("expected a map but got {:?}", value)),
+        },
+        FieldType::Map => match value {
+            serde_yaml::Value::Mapping(map) => {
+                let mut entries = vec![];
+                for (key, value) in map {
+                    let key = key.as_str().ok_or(anyhow!("map keys must be strings, got {:?}", key))?;
+                    entries.push(format!("({:?}.to_string(), {})", key, rmpv_literal(value)?));
+                }
+                format!("vec![{}].into_iter().collect()", entries.join(", "))
+            },
+            value => return Err(anyhow!("expected a map but got {:?}", value)),
+        },
+        FieldType::Any | FieldType::Scalar => rmpv_literal(value)?,
+        // seconds since the epoch
+        FieldType::Datetime => match value.as_i64() {
+            Some(seconds) => format!("tarantool_schema::types::Datetime::from_seconds({})", seconds),
+            None => return Err(anyhow!("datetime is seconds since the epoch, got {:?}", value)),
+        },
+        // units by name: {month: 1, day: 3}
+        FieldType::Interval => match value {
+            serde_yaml::Value::Mapping(map) => {
+                let mut units = vec![];
+                for (unit, value) in map {
+                    let unit = unit.as_str().filter(|unit| ["year", "month", "week", "day", "hour", "min", "sec", "nsec", "adjust"].contains(unit))
+                        .ok_or(anyhow!("unknown interval unit {:?}", unit))?;
+                    let value = value.as_i64().ok_or(anyhow!("interval unit '{}' must be an integer, got {:?}", unit, value))?;
+                    units.push(format!("{}: {}, ", unit, value));
+                }
+                format!("tarantool_schema::types::Interval {{ {}..Default::default() }}", units.concat())
+            },
+            value => return Err(anyhow!("interval is a map of units, got {:?}", value)),
+        },
+    })
+}
+
+// 8-4-4-4-12 hex digits, the dashes may be omitted
+fn parse_uuid(value: &str) -> Option<[u8; 16]> {
+    let dashed = value.len() == 36;
+    if dashed && [8, 13, 18, 23].iter().any(|position| value.as_bytes()[*position] != b'-') {
+        return None;
+    }
+    let hex: String = if dashed { value.chars().filter(|c| *c != '-').collect() } else { value.to_string() };
+    if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut bytes = [0u8; 16];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+// [+-]digits[.digits][e[+-]digits], what the decimal parser accepts
+fn is_decimal(value: &str) -> bool {
+    let value = value.strip_prefix(|c| c == '+' || c == '-').unwrap_or(value);
+    let (mantissa, exponent) = match value.find(|c| c == 'e' || c == 'E') {
+        Some(position) => (&value[..position], Some(&value[position + 1..])),
+        None => (value, None),
+    };
+    let (int, frac) = match mantissa.find('.') {
+        Some(position) => (&mantissa[..position], &mantissa[position + 1..]),
+        None => (mantissa, ""),
+    };
+    let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    !(int.is_empty() && frac.is_empty()) && digits(int) && digits(frac)
+        && exponent.map(|exponent| {
+            let exponent = exponent.strip_prefix(|c| c == '+' || c == '-').unwrap_or(exponent);
+            !exponent.is_empty() && digits(exponent)
+        }).unwrap_or(true)
+}
+
+// a seed value inside an array or a map field
+fn nested_literal(field: &Field, struct_name: &str, value: &serde_yaml::Value) -> Result<String, anyhow::Error> {
+    if value.is_null() && field.is_nullable == Some(true) {
+        return Ok(format!("None"));
+    }
+    let literal = seed_literal(field, struct_name, value, false)?;
+    let literal = match field.field_type {
+        FieldType::Varbinary => format!("serde_bytes::ByteBuf::from({})", literal),
+        _ => literal,
+    };
+    Ok(match field.is_nullable {
+        Some(true) => format!("Some({})", literal),
+        _ => literal,
+    })
+}
+
+fn rmpv_literal(value: &serde_yaml::Value) -> Result<String, anyhow::Error> {
+    Ok(match value {
+        serde_yaml::Value::Null => format!("rmpv::Value::Nil"),
+        serde_yaml::Value::Bool(value) => format!("rmpv::Value::Boolean({})", value),
+        serde_yaml::Value::Number(number) => match (number.as_u64(), number.as_i64(), number.as_f64()) {
+            (Some(value), _, _) => format!("rmpv::Value::from({}u64)", value),
+            (None, Some(value), _) => format!("rmpv::Value::from({}i64)", value),
+            (None, None, Some(value)) => format!("rmpv::Value::F64({:?})", value),
+            _ => return Err(anyhow!("unsupported number {:?}", number)),
+        },
+        serde_yaml::Value::String(value) => format!("rmpv::Value::from({:?})", value),
+        serde_yaml::Value::Sequence(items) => {
+            let mut literals = vec![];
+            for item in items {
+                literals.push(rmpv_literal(item)?);
+            }
+            format!("rmpv::Value::Array(vec![{}])", literals.join(", "))
+        },
+        serde_yaml::Value::Mapping(map) => {
+            let mut entries = vec![];
+            for (key, value) in map {
+                entries.push(format!("({}, {})", rmpv_literal(key)?, rmpv_literal(value)?));
+            }
+            format!("rmpv::Value::Map(vec![{}])", entries.join(", "))
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_decimal_accepts_decimal_numbers() {
+        for value in ["0", "-12", "+12.30", "12.", ".5", "1e10", "1.5E-3", "007"] {
+            assert!(is_decimal(value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn is_decimal_rejects_other_strings() {
+        for value in ["", "-", ".", "1.2.3", "1e", "1e+", "e5", "0x10", "1,5", "nan", " 1"] {
+            assert!(!is_decimal(value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn parse_uuid_reads_dashed_and_plain_forms() {
+        let bytes = [0x12, 0x3e, 0x45, 0x67, 0xe8, 0x9b, 0x12, 0xd3, 0xa4, 0x56, 0x42, 0x66, 0x14, 0x17, 0x40, 0x00];
+        assert_eq!(parse_uuid("123e4567-e89b-12d3-a456-426614174000"), Some(bytes));
+        assert_eq!(parse_uuid("123E4567E89B12D3A456426614174000"), Some(bytes));
+    }
+
+    #[test]
+    fn parse_uuid_rejects_malformed_values() {
+        for value in ["", "123e4567-e89b-12d3-a456-42661417400", "123e4567e89b-12d3-a456-4266141740000",
+                      "123e4567-e89b-12d3-a456-42661417400g", "123e4567-e89b-12d3-a456_426614174000"] {
+            assert_eq!(parse_uuid(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn validate_index_types_reports_every_violation() {
+        let mut space: Space = serde_yaml::from_str(r#"
+format:
+  id: {type: unsigned, is_nullable: true}
+  tags: {type: array}
+indexes:
+  by_id:
+    type: tree
+    unique: false
+    parts: [{field: id, type: unsigned}]
+  primary:
+    type: hash
+    unique: false
+    parts: [{field: id, type: unsigned}]
+  by_tags:
+    type: bitset
+    unique: true
+    parts: [{field: tags, type: array}]
+"#).unwrap();
+        space.name = format!("users");
+        let message = space.validate_index_types().unwrap_err().to_string();
+        for expected in [
+            "Index 'by_id' from space 'users' is primary so it must be unique",
+            "Index 'by_id' from space 'users' is primary so its parts can't be nullable",
+            "Index 'primary' from space 'users' is named 'primary' but the primary index must be declared first",
+            "Index 'primary' from space 'users' is hash so it must be unique",
+            "Index 'primary' from space 'users' is hash so its parts can't be nullable",
+            "Index 'by_tags' from space 'users' is bitset so it can't be unique",
+            "Index 'by_tags' from space 'users' is bitset so it must have exactly one unsigned or string part",
+        ] {
+            assert!(message.contains(expected), "'{}' isn't in:\n{}", expected, message);
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use crate::live::{LiveField, LiveIndex, LiveSequence, LiveSpace};
+use crate::{Field, Index, Sequence, Space};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexChange {
+    Type { declared: String, live: String },
+    Unique { declared: bool, live: bool },
+    PartCount { declared: usize, live: usize },
+    Option { option: &'static str, declared: String, live: String },
+    Part { part: usize, property: &'static str, declared: String, live: String },
+}
+impl std::fmt::Display for IndexChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexChange::Type { declared, live } => write!(f, "type is '{}' but declared '{}'", live, declared),
+            IndexChange::Unique { declared, live } => write!(f, "unique is {} but declared {}", live, declared),
+            IndexChange::PartCount { declared, live } => write!(f, "has {} parts but declared {}", live, declared),
+            IndexChange::Option { option, declared, live } => write!(f, "{} is '{}' but declared '{}'", option, live, declared),
+            IndexChange::Part { part, property, declared, live } => write!(f, "part #{} {} is '{}' but declared '{}'", part, property, live, declared),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    SpaceAdded,
+    EngineChanged { declared: String, live: String },
+    FlagChanged { flag: &'static str, declared: bool, live: bool },
+    OptionChanged { option: &'static str, declared: String, live: String },
+    FieldAdded { position: usize, field: Field },
+    FieldRemoved { position: usize, field: LiveField },
+    FieldRenamed { position: usize, declared: String, live: String },
+    FieldTypeChanged { name: String, declared: String, live: String },
+    FieldNullableChanged { name: String, declared: bool, live: bool },
+    FieldCollationChanged { name: String, declared: Option<String>, live: Option<String> },
+    IndexAdded { index: Index },
+    IndexRemoved { index: LiveIndex },
+    IndexAltered { index: Index, live: LiveIndex, changes: Vec<IndexChange> },
+    SequenceAdded { sequence: Sequence },
+    SequenceRemoved { sequence: LiveSequence },
+    SequenceAltered { sequence: String, property: &'static str, declared: String, live: String },
+}
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::SpaceAdded => write!(f, "space doesn't exist"),
+            Change::EngineChanged { declared, live } => write!(f, "engine is '{}' but declared '{}'", live, declared),
+            Change::FlagChanged { flag, declared, live } => write!(f, "'{}' is {} but declared {}", flag, live, declared),
+            Change::OptionChanged { option, declared, live } => write!(f, "'{}' is '{}' but declared '{}'", option, live, declared),
+            Change::FieldAdded { position, field } => write!(f, "field #{} '{}' doesn't exist", position, field.name),
+            Change::FieldRemoved { position, field } => write!(f, "field #{} '{}' isn't declared", position, field.name),
+            Change::FieldRenamed { position, declared, live } => write!(f, "field #{} is '{}' but declared '{}'", position, live, declared),
+            Change::FieldTypeChanged { name, declared, live } => write!(f, "field '{}' has type '{}' but declared '{}'", name, live, declared),
+            Change::FieldNullableChanged { name, declared, live } => write!(f, "field '{}' is_nullable is {} but declared {}", name, live, declared),
+            Change::FieldCollationChanged { name, declared, live } => write!(f, "field '{}' collation is '{}' but declared '{}'", name, live.clone().unwrap_or_default(), declared.clone().unwrap_or_default()),
+            Change::IndexAdded { index } => write!(f, "index '{}' doesn't exist", index.name),
+            Change::IndexRemoved { index } => write!(f, "index '{}' isn't declared", index.name),
+            Change::SequenceAdded { sequence } => write!(f, "sequence '{}' doesn't exist", sequence.name),
+            Change::SequenceRemoved { sequence } => write!(f, "sequence '{}' isn't declared", sequence.name),
+            Change::SequenceAltered { sequence, property, declared, live } => write!(f, "sequence '{}' {} is '{}' but declared '{}'", sequence, property, live, declared),
+            Change::IndexAltered { index, changes, .. } => {
+                write!(f, "index '{}':", index.name)?;
+                for (n, change) in changes.iter().enumerate() {
+                    write!(f, "{} {}", if n == 0 { "" } else { "," }, change)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+// tarantool stores type names in lower case: "unsigned", "tree", "memtx"
+pub(crate) fn type_name<T: std::fmt::Debug>(value: &T) -> String {
+    format!("{:?}", value).to_lowercase()
+}
+
+fn diff_index(index: &Index, live: &LiveIndex) -> Vec<IndexChange> {
+    let mut changes = vec![];
+    let declared_type = type_name(&index.index_type);
+    if declared_type != live.index_type {
+        changes.push(IndexChange::Type { declared: declared_type, live: live.index_type.clone() });
+    }
+    if index.unique != live.unique {
+        changes.push(IndexChange::Unique { declared: index.unique, live: live.unique });
+    }
+    // vinyl options are compared only when declared, tarantool fills in the defaults
+    for (option, declared, live) in [("bloom_fpr", index.bloom_fpr.map(|v| v as f64), live.bloom_fpr), ("run_size_ratio", index.run_size_ratio.map(|v| v as f64), live.run_size_ratio)] {
+        if let Some(declared) = declared {
+            if live.map(|live| (live - declared).abs() > 1e-6).unwrap_or(true) {
+                changes.push(IndexChange::Option { option, declared: format!("{}", declared), live: live.map(|live| format!("{}", live)).unwrap_or_default() });
+            }
+        }
+    }
+    for (option, declared, live) in [("page_size", index.page_size, live.page_size), ("range_size", index.range_size, live.range_size),
+                                     ("run_count_per_level", index.run_count_per_level, live.run_count_per_level)] {
+        if let Some(declared) = declared {
+            if live != Some(declared as u64) {
+                changes.push(IndexChange::Option { option, declared: format!("{}", declared), live: live.map(|live| format!("{}", live)).unwrap_or_default() });
+            }
+        }
+    }
+    if index.func != live.func {
+        changes.push(IndexChange::Option { option: "func", declared: index.func.clone().unwrap_or_default(), live: live.func.clone().unwrap_or_default() });
+    }
+    if let Some(dimension) = index.dimension {
+        if live.dimension != Some(dimension as u64) {
+            changes.push(IndexChange::Option { option: "dimension", declared: format!("{}", dimension), live: live.dimension.map(|live| format!("{}", live)).unwrap_or_default() });
+        }
+    }
+    if let Some(distance) = &index.distance {
+        let distance = type_name(distance);
+        if live.distance.as_ref() != Some(&distance) {
+            changes.push(IndexChange::Option { option: "distance", declared: distance, live: live.distance.clone().unwrap_or_default() });
+        }
+    }
+    if index.parts.len() != live.parts.len() {
+        changes.push(IndexChange::PartCount { declared: index.parts.len(), live: live.parts.len() });
+        return changes;
+    }
+    for (part_index, (part, live_part)) in index.parts.iter().zip(live.parts.iter()).enumerate() {
+        let mut check = |property: &'static str, declared: String, live: String| {
+            if declared != live {
+                changes.push(IndexChange::Part { part: part_index + 1, property, declared, live });
+            }
+        };
+        let part = &part.part;
+        check("field", format!("{}", part.field_index), format!("{}", live_part.field_index));
+        check("type", type_name(&part.field_type), live_part.field_type.clone());
+        check("is_nullable", format!("{}", part.is_nullable.unwrap_or(false)), format!("{}", live_part.is_nullable));
+        check("collation", part.collation.clone().unwrap_or_default(), live_part.collation.clone().unwrap_or_default());
+        check("path", part.path.clone().unwrap_or_default(), live_part.path.clone().unwrap_or_default());
+    }
+    changes
+}
+
+impl Space {
+    pub fn diff(&self) -> Result<Vec<Change>, anyhow::Error> {
+        match LiveSpace::find(&self.name)? {
+            Some(live) => Ok(self.diff_with(&live)),
+            None => Ok(vec![Change::SpaceAdded]),
+        }
+    }
+
+    pub fn diff_with(&self, live: &LiveSpace) -> Vec<Change> {
+        let mut changes = vec![];
+
+        let engine = type_name(&self.engine);
+        if engine != live.engine {
+            changes.push(Change::EngineChanged { declared: engine, live: live.engine.clone() });
+        }
+        if self.is_local != live.is_local {
+            changes.push(Change::FlagChanged { flag: "is_local", declared: self.is_local, live: live.is_local });
+        }
+        if self.temporary != live.temporary {
+            changes.push(Change::FlagChanged { flag: "temporary", declared: self.temporary, live: live.temporary });
+        }
+        if self.defer_deletes != live.defer_deletes {
+            changes.push(Change::FlagChanged { flag: "defer_deletes", declared: self.defer_deletes, live: live.defer_deletes });
+        }
+        if self.is_sync != live.is_sync {
+            changes.push(Change::FlagChanged { flag: "is_sync", declared: self.is_sync, live: live.is_sync });
+        }
+        if self.field_count != live.field_count {
+            changes.push(Change::OptionChanged { option: "field_count", declared: format!("{}", self.field_count), live: format!("{}", live.field_count) });
+        }
+        if let Some(id) = self.id {
+            if id != live.id {
+                changes.push(Change::OptionChanged { option: "id", declared: format!("{}", id), live: format!("{}", live.id) });
+            }
+        }
+        if let Some(user) = &self.user {
+            if user != &live.owner {
+                changes.push(Change::OptionChanged { option: "user", declared: user.clone(), live: live.owner.clone() });
+            }
+        }
+
+        for (position, field) in self.format.iter().enumerate() {
+            let live_field = match live.format.get(position) {
+                Some(live_field) => live_field,
+                None => {
+                    changes.push(Change::FieldAdded { position: position + 1, field: field.clone() });
+                    continue;
+                }
+            };
+            if live_field.name != field.name {
+                changes.push(Change::FieldRenamed { position: position + 1, declared: field.name.clone(), live: live_field.name.clone() });
+            }
+            let field_type = type_name(&field.field_type);
+            if field_type != live_field.field_type {
+                changes.push(Change::FieldTypeChanged { name: field.name.clone(), declared: field_type, live: live_field.field_type.clone() });
+            }
+            let is_nullable = field.is_nullable.unwrap_or(false);
+            if is_nullable != live_field.is_nullable {
+                changes.push(Change::FieldNullableChanged { name: field.name.clone(), declared: is_nullable, live: live_field.is_nullable });
+            }
+            if field.collation != live_field.collation {
+                changes.push(Change::FieldCollationChanged { name: field.name.clone(), declared: field.collation.clone(), live: live_field.collation.clone() });
+            }
+        }
+        for (position, live_field) in live.format.iter().enumerate().skip(self.format.len()) {
+            changes.push(Change::FieldRemoved { position: position + 1, field: live_field.clone() });
+        }
+
+        for index in &self.indexes {
+            match live.index(&index.name) {
+                Some(live_index) => {
+                    let index_changes = diff_index(index, live_index);
+                    if !index_changes.is_empty() {
+                        changes.push(Change::IndexAltered { index: index.clone(), live: live_index.clone(), changes: index_changes });
+                    }
+                },
+                None => changes.push(Change::IndexAdded { index: index.clone() }),
+            }
+        }
+        for live_index in &live.indexes {
+            if !self.indexes.iter().any(|index| index.name == live_index.name) {
+                changes.push(Change::IndexRemoved { index: live_index.clone() });
+            }
+        }
+
+        match (&self.sequence, &live.sequence) {
+            (Some(sequence), Some(live_sequence)) => {
+                let mut check = |property: &'static str, declared: String, live: String| {
+                    if declared != live {
+                        changes.push(Change::SequenceAltered { sequence: sequence.name.clone(), property, declared, live });
+                    }
+                };
+                check("name", sequence.name.clone(), live_sequence.name.clone());
+                check("field", format!("{}", sequence.field_index), format!("{}", live_sequence.field_index.unwrap_or(0)));
+                check("cycle", format!("{}", sequence.cycle), format!("{}", live_sequence.cycle));
+                for (property, declared, live) in [("start", sequence.start, live_sequence.start), ("step", sequence.step, live_sequence.step),
+                                                   ("min", sequence.min, live_sequence.min), ("max", sequence.max, live_sequence.max)] {
+                    if let Some(declared) = declared {
+                        check(property, format!("{}", declared), format!("{}", live));
+                    }
+                }
+            },
+            (Some(sequence), None) => changes.push(Change::SequenceAdded { sequence: sequence.clone() }),
+            (None, Some(live_sequence)) => changes.push(Change::SequenceRemoved { sequence: live_sequence.clone() }),
+            (None, None) => {},
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::LiveIndexPart;
+    use crate::FieldType;
+
+    fn declared() -> Space {
+        crate::migrations::internal_space("users", vec![("id", FieldType::Unsigned), ("name", FieldType::String)])
+    }
+
+    fn live_field(name: &str, field_type: &str) -> LiveField {
+        LiveField { name: name.to_string(), field_type: field_type.to_string(), is_nullable: false, collation: None }
+    }
+
+    fn live_index(id: u32, name: &str, field_index: u32, field_type: &str) -> LiveIndex {
+        LiveIndex {
+            id,
+            name: name.to_string(),
+            index_type: "tree".to_string(),
+            unique: true,
+            parts: vec![LiveIndexPart { field_index, field_type: field_type.to_string(), is_nullable: false, collation: None, path: None }],
+            bloom_fpr: None,
+            page_size: None,
+            range_size: None,
+            run_count_per_level: None,
+            run_size_ratio: None,
+            func: None,
+            dimension: None,
+            distance: None,
+        }
+    }
+
+    // what `declared()` looks like once created
+    fn live() -> LiveSpace {
+        LiveSpace {
+            id: 512,
+            name: "users".to_string(),
+            owner: "admin".to_string(),
+            engine: "memtx".to_string(),
+            field_count: 0,
+            is_local: false,
+            temporary: false,
+            is_sync: false,
+            defer_deletes: false,
+            format: vec![live_field("id", "unsigned"), live_field("name", "string")],
+            indexes: vec![live_index(0, "primary", 1, "unsigned")],
+            sequence: None,
+        }
+    }
+
+    fn live_sequence(step: i64) -> LiveSequence {
+        LiveSequence { id: 1, name: "users_seq".to_string(), step, min: 1, max: i64::MAX, start: 1, cycle: false, field_index: Some(1), path: None }
+    }
+
+    fn sequence() -> Sequence {
+        Sequence { name: "users_seq".to_string(), field_index: 1, start: None, step: Some(1), min: None, max: None, cycle: false }
+    }
+
+    #[test]
+    fn same_space_has_no_changes() {
+        let changes = declared().diff_with(&live());
+        assert!(changes.is_empty(), "{:?}", changes);
+    }
+
+    #[test]
+    fn fields_added_and_removed() {
+        let mut space = declared();
+        space.format.push(Field { name: "email".to_string(), is_nullable: Some(true), field_type: FieldType::String, collation: None });
+        let changes = space.diff_with(&live());
+        assert!(matches!(changes.as_slice(), [Change::FieldAdded { position: 3, field }] if field.name == "email"), "{:?}", changes);
+
+        let mut live = live();
+        live.format.push(live_field("email", "string"));
+        let changes = declared().diff_with(&live);
+        assert!(matches!(changes.as_slice(), [Change::FieldRemoved { position: 3, field }] if field.name == "email"), "{:?}", changes);
+    }
+
+    #[test]
+    fn field_renamed_retyped_and_made_nullable() {
+        let mut live = live();
+        live.format[1] = LiveField { name: "title".to_string(), field_type: "any".to_string(), is_nullable: true, collation: None };
+        let changes = declared().diff_with(&live);
+        assert!(matches!(changes.as_slice(), [
+            Change::FieldRenamed { position: 2, declared, live },
+            Change::FieldTypeChanged { name, declared: declared_type, live: live_type },
+            Change::FieldNullableChanged { declared: false, live: true, .. },
+        ] if declared == "name" && live == "title" && name == "name" && declared_type == "string" && live_type == "any"), "{:?}", changes);
+    }
+
+    #[test]
+    fn indexes_added_removed_and_altered() {
+        let mut space = declared();
+        let mut by_name = space.indexes[0].clone();
+        by_name.name = "by_name".to_string();
+        by_name.parts[0].part.field_index = 2;
+        by_name.parts[0].part.field_type = tarantool::index::IndexFieldType::String;
+        space.indexes.push(by_name);
+
+        let mut live = live();
+        live.indexes[0].unique = false;
+        live.indexes[0].parts[0].field_type = "integer".to_string();
+        live.indexes.push(live_index(1, "by_email", 3, "string"));
+
+        let changes = space.diff_with(&live);
+        assert_eq!(changes.len(), 3, "{:?}", changes);
+        match &changes[0] {
+            Change::IndexAltered { index, changes, .. } => {
+                assert_eq!(index.name, "primary");
+                assert_eq!(changes, &vec![
+                    IndexChange::Unique { declared: true, live: false },
+                    IndexChange::Part { part: 1, property: "type", declared: "unsigned".to_string(), live: "integer".to_string() },
+                ]);
+            },
+            change => panic!("unexpected {}", change),
+        }
+        assert!(matches!(&changes[1], Change::IndexAdded { index } if index.name == "by_name"), "{:?}", changes);
+        assert!(matches!(&changes[2], Change::IndexRemoved { index } if index.name == "by_email"), "{:?}", changes);
+    }
+
+    #[test]
+    fn sequences_added_removed_and_altered() {
+        let mut space = declared();
+        space.sequence = Some(sequence());
+        let changes = space.diff_with(&live());
+        assert!(matches!(changes.as_slice(), [Change::SequenceAdded { .. }]), "{:?}", changes);
+
+        let mut live = live();
+        live.sequence = Some(live_sequence(2));
+        let changes = space.diff_with(&live);
+        assert!(matches!(changes.as_slice(), [Change::SequenceAltered { property: "step", declared, live, .. }] if declared == "1" && live == "2"), "{:?}", changes);
+
+        let changes = declared().diff_with(&live);
+        assert!(matches!(changes.as_slice(), [Change::SequenceRemoved { .. }]), "{:?}", changes);
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+use serde::ser::SerializeSeq;
+
+// Keys of RTREE indexes. A point is `dimension` coordinates, a rectangle is two
+// opposite corners; tarantool takes both as flat arrays of numbers.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point<const N: usize>(pub [f64; N]);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect<const N: usize> {
+    pub min: Point<N>,
+    pub max: Point<N>,
+}
+impl<const N: usize> Rect<N> {
+    pub fn new(min: Point<N>, max: Point<N>) -> Self {
+        Rect { min, max }
+    }
+}
+
+impl<const N: usize> Serialize for Point<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(N))?;
+        for coord in &self.0 {
+            seq.serialize_element(coord)?;
+        }
+        seq.end()
+    }
+}
+impl<'de, const N: usize> Deserialize<'de> for Point<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let coords = Vec::<f64>::deserialize(deserializer)?;
+        let len = coords.len();
+        let coords: [f64; N] = coords.try_into().map_err(|_| D::Error::invalid_length(len, &"point coordinates"))?;
+        Ok(Point(coords))
+    }
+}
+
+impl<const N: usize> Serialize for Rect<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(N * 2))?;
+        for coord in self.min.0.iter().chain(self.max.0.iter()) {
+            seq.serialize_element(coord)?;
+        }
+        seq.end()
+    }
+}
+impl<'de, const N: usize> Deserialize<'de> for Rect<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let coords = Vec::<f64>::deserialize(deserializer)?;
+        if coords.len() != N * 2 {
+            return Err(D::Error::invalid_length(coords.len(), &"two corners of the rectangle"));
+        }
+        let mut min = [0.0; N];
+        let mut max = [0.0; N];
+        min.copy_from_slice(&coords[..N]);
+        max.copy_from_slice(&coords[N..]);
+        Ok(Rect { min: Point(min), max: Point(max) })
+    }
+}
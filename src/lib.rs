@@ -3,15 +3,83 @@ use anyhow::anyhow;
 #[cfg(feature = "codegen")]
 pub mod codegen;
 
+pub mod geo;
+pub mod live;
+pub mod diff;
+pub mod migrations;
+pub mod types;
+mod alter;
+mod backfill;
+mod lua;
+mod transaction;
+mod verify;
+pub use transaction::transaction;
+pub use verify::VerifyError;
+
+// Space field types. Unlike `tarantool::space::SpaceFieldType` it has `map`, `varbinary`,
+// `datetime` and `interval`; the format is set from Lua so any type tarantool knows can be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    Any,
+    Unsigned,
+    String,
+    Number,
+    Double,
+    Integer,
+    Boolean,
+    Decimal,
+    Uuid,
+    Varbinary,
+    Array,
+    Map,
+    Scalar,
+    Datetime,
+    Interval,
+}
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", diff::type_name(self))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Field {
     pub name: String,
     pub is_nullable: Option<bool>,
-    pub field_type: tarantool::space::SpaceFieldType,
+    pub field_type: FieldType,
+    pub collation: Option<String>,
+}
+#[derive(Debug, Clone)]
+pub struct Collation {
+    pub name: String,
+    pub locale: String,
+    // ICU options: strength, numeric_collation, case_first, ...
+    pub options: Vec<(String, String)>,
 }
-impl Field {
-    fn format(&self) -> tarantool::space::SpaceFieldFormat {
-        tarantool::space::SpaceFieldFormat { name: self.name.to_string(), field_type : self.field_type.clone(), is_nullable: self.is_nullable }
+impl Collation {
+    // Creates the collation unless it already exists
+    pub fn ensure(&self) -> Result<(), anyhow::Error> {
+        lua::exec(&format!("if box.space._collation.index.name:get{{{}}} == nil then box.internal.collation.create({}, 'ICU', {}, {}) end",
+                           lua::string(&self.name), lua::string(&self.name), lua::string(&self.locale), lua::collation_options(&self.options)))
+            .map_err(|err| anyhow!("Can't create collation '{}': {}", self.name, err))
+    }
+}
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: String,
+    // "lua" with a body, or "c" for a function exported from a module (`module.symbol`)
+    pub language: String,
+    pub body: Option<String>,
+    pub is_deterministic: bool,
+    pub is_sandboxed: bool,
+    pub is_multikey: bool,
+}
+impl Function {
+    // Creates the function unless it already exists
+    pub fn ensure(&self) -> Result<(), anyhow::Error> {
+        lua::exec(&format!("box.schema.func.create({}, {})", lua::string(&self.name), lua::function_options(self)))
+            .map_err(|err| anyhow!("Can't create function '{}': {}", self.name, err))
     }
 }
 #[derive(Debug, Clone)]
@@ -27,6 +95,64 @@ pub struct Index {
     pub index_type: tarantool::index::IndexType,
     pub unique: bool,
     pub parts: Vec<IndexPart>,
+    // vinyl only
+    pub bloom_fpr: Option<f32>,
+    pub page_size: Option<u32>,
+    pub range_size: Option<u32>,
+    pub run_count_per_level: Option<u32>,
+    pub run_size_ratio: Option<f32>,
+    // functional index: the function returns the key, parts describe its fields
+    pub func: Option<String>,
+    // rtree only
+    pub dimension: Option<u32>,
+    pub distance: Option<tarantool::index::RtreeIndexDistanceType>,
+}
+impl Index {
+    fn options(&self) -> tarantool::index::IndexOptions {
+        let mut opts = tarantool::index::IndexOptions::default();
+        opts.if_not_exists = Some(false);
+        opts.unique = Some(self.unique);
+        opts.index_type = Some(self.index_type.clone());
+        let mut parts = vec![];
+        for part in &self.parts {
+            parts.push(part.part.clone());
+        }
+        opts.parts = Some(parts);
+        opts.bloom_fpr = self.bloom_fpr;
+        opts.page_size = self.page_size;
+        opts.range_size = self.range_size;
+        opts.run_count_per_level = self.run_count_per_level;
+        opts.run_size_ratio = self.run_size_ratio;
+        opts.func = self.func.clone();
+        opts.dimension = self.dimension;
+        opts.distance = self.distance.clone();
+        opts
+    }
+}
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    pub name: String,
+    // part of the primary index the sequence fills, 1-based
+    pub field_index: u32,
+    pub start: Option<i64>,
+    pub step: Option<i64>,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    pub cycle: bool,
+}
+impl Sequence {
+    fn create(&self, space_name: &str, index_name: &str) -> Result<(), anyhow::Error> {
+        lua::exec(&format!("box.schema.sequence.create({}, {})", lua::string(&self.name), lua::sequence_options(self)))
+            .map_err(|err| anyhow!("Can't create sequence '{}' of space '{}': {}", self.name, space_name, err))?;
+        let index = lua::index(space_name, index_name);
+        lua::exec(&format!("{}:alter({{sequence = {{id = {}, field = {}}}}})", index, lua::string(&self.name), self.field_index))
+            .map_err(|err| anyhow!("Can't attach sequence '{}' to space '{}': {}", self.name, space_name, err))?;
+        // continue after the keys already stored in the space
+        lua::exec(&format!("local max = {}:max() if max ~= nil then box.sequence[{}]:set(max[{}]) end", index, lua::string(&self.name), self.field_index))
+    }
+    fn drop(&self) -> Result<(), anyhow::Error> {
+        lua::exec(&format!("if box.sequence[{}] ~= nil then box.sequence[{}]:drop() end", lua::string(&self.name), lua::string(&self.name)))
+    }
 }
 #[derive(Debug, Clone)]
 pub struct Space {
@@ -34,43 +160,88 @@ pub struct Space {
     pub engine: tarantool::space::SpaceEngineType,
     pub is_local: bool,
     pub temporary: bool,
+    pub is_sync: bool,
+    pub id: Option<u32>,
+    pub field_count: u32,
+    pub user: Option<String>,
+    // vinyl only
+    pub defer_deletes: bool,
     pub format: Vec<Field>,
     pub indexes: Vec<Index>,
+    pub sequence: Option<Sequence>,
 }
 impl Space {
     pub fn create(&self) -> Result<(), anyhow::Error> {
         let mut opts = tarantool::space::SpaceCreateOptions::default();
         opts.if_not_exists = false;
-        let mut format = vec![];
-        for field in &self.format {
-            format.push(field.format());
+        opts.engine = self.engine.clone();
+        opts.is_local = self.is_local;
+        opts.temporary = self.temporary;
+        opts.is_sync = self.is_sync;
+        opts.id = self.id;
+        opts.field_count = self.field_count;
+        opts.user = self.user.clone();
+        let space = tarantool::space::Space::create(&self.name, &opts)
+            .map_err(|err| anyhow!("Can't create space '{}': {}", self.name, err))?;
+        // SpaceFieldFormat can't carry collations, the format is set from Lua
+        self.set_format()?;
+        if self.defer_deletes {
+            lua::exec(&format!("{}:alter({{defer_deletes = true}})", lua::space(&self.name)))
+                .map_err(|err| anyhow!("Can't set defer_deletes of space '{}': {}", self.name, err))?;
         }
-        opts.format = Some(format);
-        let space = tarantool::space::Space::create(&self.name, &opts)?;
 
         for index in &self.indexes {
-            let mut opts = tarantool::index::IndexOptions::default();
-            opts.if_not_exists = Some(false);
-            opts.unique = Some(index.unique);
-            opts.index_type = Some(index.index_type.clone());
-            let mut parts = vec![];
-            for part in &index.parts {
-                parts.push(part.part.clone());
-            }
-            opts.parts = Some(parts);
-            space.create_index(&index.name, &opts)?;
+            space.create_index(&index.name, &index.options())
+                .map_err(|err| anyhow!("Can't create index '{}' of space '{}': {}", index.name, self.name, err))?;
         }
+        self.create_sequence()?;
 
         Ok(())
     }
+    pub(crate) fn create_sequence(&self) -> Result<(), anyhow::Error> {
+        match (&self.sequence, self.indexes.first()) {
+            (Some(sequence), Some(primary)) => sequence.create(&self.name, &primary.name),
+            (Some(sequence), None) => Err(anyhow!("Can't attach sequence '{}' to space '{}' without primary index", sequence.name, self.name)),
+            (None, _) => Ok(()),
+        }
+    }
     pub fn space(&self) -> Result<tarantool::space::Space, anyhow::Error> {
         tarantool::space::Space::find(&self.name).ok_or(anyhow!("Can't find space '{}'", self.name))
     }
-    pub fn drop(&self) -> Result<(), anyhow::Error> { self.space()?.drop()?; Ok(()) }
+    pub fn drop(&self) -> Result<(), anyhow::Error> {
+        self.space()?.drop()?;
+        if let Some(sequence) = &self.sequence {
+            sequence.drop()?;
+        }
+        Ok(())
+    }
     pub fn truncate(&self) -> Result<(), anyhow::Error> { self.space()?.truncate()?; Ok(()) }
 
+    // Inserts the row with an empty sequence field and returns the generated value
+    pub fn insert_auto<T: serde::Serialize>(&self, row: &T) -> Result<u64, anyhow::Error> {
+        let sequence = self.sequence.as_ref().ok_or(anyhow!("Space '{}' has no sequence", self.name))?;
+        let field = sequence.field_index as usize - 1;
+        let mut tuple = match rmpv::ext::to_value(row)? {
+            rmpv::Value::Array(tuple) => tuple,
+            value => return Err(anyhow!("Row of space '{}' isn't serialized as an array: {}", self.name, value)),
+        };
+        match tuple.get_mut(field) {
+            Some(value) => *value = rmpv::Value::Nil,
+            None => return Err(anyhow!("Row of space '{}' has no field #{}", self.name, sequence.field_index)),
+        }
+        let inserted = self.space()?.insert(&tuple)?
+            .ok_or(anyhow!("Insert into space '{}' returned no tuple", self.name))?
+            .as_struct::<Vec<rmpv::Value>>()?;
+        inserted.get(field).and_then(|value| value.as_u64())
+            .ok_or(anyhow!("Space '{}' returned no generated value in field #{}", self.name, sequence.field_index))
+    }
+
     pub fn verify(&self) -> Result<(), anyhow::Error> {
-        Ok(())
+        let changes = self.diff()?;
+        if changes.is_empty() {
+            return Ok(());
+        }
+        Err(VerifyError { space: self.name.clone(), changes }.into())
     }
 }
 
@@ -0,0 +1,365 @@
+use anyhow::anyhow;
+use serde::Deserialize;
+use tarantool::index::IteratorType;
+
+// Snapshot of a space as it is stored in the `_space` / `_index` system spaces.
+// Types are kept as the raw names tarantool uses, so a drifted schema with types
+// unknown to this crate can still be read and reported.
+
+#[derive(Debug, Clone)]
+pub struct LiveField {
+    pub name: String,
+    pub field_type: String,
+    pub is_nullable: bool,
+    pub collation: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LiveIndexPart {
+    // 1-based like `tarantool::index::IndexPart::field_index`
+    pub field_index: u32,
+    pub field_type: String,
+    pub is_nullable: bool,
+    pub collation: Option<String>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LiveIndex {
+    pub id: u32,
+    pub name: String,
+    pub index_type: String,
+    pub unique: bool,
+    pub parts: Vec<LiveIndexPart>,
+    pub bloom_fpr: Option<f64>,
+    pub page_size: Option<u64>,
+    pub range_size: Option<u64>,
+    pub run_count_per_level: Option<u64>,
+    pub run_size_ratio: Option<f64>,
+    // name of the function of a functional index
+    pub func: Option<String>,
+    // rtree only
+    pub dimension: Option<u64>,
+    pub distance: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LiveSequence {
+    pub id: u32,
+    pub name: String,
+    pub step: i64,
+    pub min: i64,
+    pub max: i64,
+    pub start: i64,
+    pub cycle: bool,
+    // set when the sequence is attached to the space, 1-based
+    pub field_index: Option<u32>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LiveCollation {
+    pub id: u32,
+    pub name: String,
+    pub locale: String,
+    pub options: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LiveFunction {
+    pub id: u32,
+    pub name: String,
+    // lower case: "lua", "c"
+    pub language: String,
+    pub body: Option<String>,
+    pub is_deterministic: bool,
+    pub is_sandboxed: bool,
+    pub is_multikey: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct LiveSpace {
+    pub id: u32,
+    pub name: String,
+    pub owner: String,
+    pub engine: String,
+    pub field_count: u32,
+    pub is_local: bool,
+    pub temporary: bool,
+    pub is_sync: bool,
+    pub defer_deletes: bool,
+    pub format: Vec<LiveField>,
+    pub indexes: Vec<LiveIndex>,
+    pub sequence: Option<LiveSequence>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpaceTuple(u32, u32, String, String, u32, rmpv::Value, Vec<rmpv::Value>);
+
+#[derive(Debug, Deserialize)]
+struct IndexTuple(u32, u32, String, String, rmpv::Value, Vec<rmpv::Value>);
+
+fn system_space(name: &str) -> Result<tarantool::space::Space, anyhow::Error> {
+    tarantool::space::Space::find(name).ok_or(anyhow!("Can't find system space '{}'", name))
+}
+
+fn map_get<'a>(map: &'a rmpv::Value, key: &str) -> Option<&'a rmpv::Value> {
+    map.as_map()?.iter().find(|(k, _)| k.as_str() == Some(key)).map(|(_, v)| v)
+}
+fn map_bool(map: &rmpv::Value, key: &str) -> bool {
+    map_get(map, key).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+fn user_name(id: u32) -> Result<String, anyhow::Error> {
+    // _vuser is readable by any user, unlike _user
+    let tuple = system_space("_vuser")?.get(&(id,))?
+        .ok_or(anyhow!("Can't find user with id {}", id))?;
+    // [id, owner, name, type, auth]
+    let user = tuple.as_struct::<Vec<rmpv::Value>>()?;
+    user.get(2).and_then(|v| v.as_str()).map(|name| name.to_string()).ok_or(anyhow!("Incorrect _vuser tuple with id {}", id))
+}
+
+fn collation_name(id: u32) -> Result<String, anyhow::Error> {
+    let tuple = system_space("_collation")?.get(&(id,))?
+        .ok_or(anyhow!("Can't find collation with id {}", id))?;
+    // [id, name, owner, type, locale, opts]
+    let collation = tuple.as_struct::<Vec<rmpv::Value>>()?;
+    collation.get(1).and_then(|v| v.as_str()).map(|name| name.to_string()).ok_or(anyhow!("Incorrect _collation tuple with id {}", id))
+}
+fn collation(map: &rmpv::Value) -> Result<Option<String>, anyhow::Error> {
+    match map_get(map, "collation").and_then(|v| v.as_u64()) {
+        Some(id) => Ok(Some(collation_name(id as u32)?)),
+        None => Ok(None),
+    }
+}
+
+impl LiveField {
+    fn from_value(value: &rmpv::Value) -> Result<Self, anyhow::Error> {
+        let name = map_get(value, "name").and_then(|v| v.as_str())
+            .ok_or(anyhow!("Space format entry has no name: {}", value))?;
+        let field_type = map_get(value, "type").and_then(|v| v.as_str()).unwrap_or("any");
+        Ok(LiveField {
+            name: name.to_string(),
+            field_type: field_type.to_string(),
+            is_nullable: map_bool(value, "is_nullable"),
+            collation: collation(value)?,
+        })
+    }
+}
+
+impl LiveIndexPart {
+    fn from_value(value: &rmpv::Value) -> Result<Self, anyhow::Error> {
+        // tarantool 1.6 stored parts as [field_no, type], newer versions use a map
+        if let Some(array) = value.as_array() {
+            let field = array.get(0).and_then(|v| v.as_u64()).ok_or(anyhow!("Incorrect index part: {}", value))?;
+            let field_type = array.get(1).and_then(|v| v.as_str()).ok_or(anyhow!("Incorrect index part: {}", value))?;
+            return Ok(LiveIndexPart {
+                field_index: field as u32 + 1,
+                field_type: field_type.to_string(),
+                is_nullable: false,
+                collation: None,
+                path: None,
+            });
+        }
+        let field = map_get(value, "field").and_then(|v| v.as_u64()).ok_or(anyhow!("Incorrect index part: {}", value))?;
+        let field_type = map_get(value, "type").and_then(|v| v.as_str()).ok_or(anyhow!("Incorrect index part: {}", value))?;
+        Ok(LiveIndexPart {
+            field_index: field as u32 + 1,
+            field_type: field_type.to_string(),
+            is_nullable: map_bool(value, "is_nullable"),
+            collation: collation(value)?,
+            path: map_get(value, "path").and_then(|v| v.as_str()).map(|path| path.to_string()),
+        })
+    }
+}
+
+impl LiveIndex {
+    fn from_tuple(tuple: IndexTuple) -> Result<Self, anyhow::Error> {
+        let IndexTuple(_, id, name, index_type, opts, parts) = tuple;
+        let mut live_parts = vec![];
+        for part in &parts {
+            live_parts.push(LiveIndexPart::from_value(part)?);
+        }
+        Ok(LiveIndex {
+            id,
+            name: name.clone(),
+            index_type: index_type.to_lowercase(),
+            unique: map_bool(&opts, "unique"),
+            parts: live_parts,
+            bloom_fpr: map_get(&opts, "bloom_fpr").and_then(|v| v.as_f64()),
+            page_size: map_get(&opts, "page_size").and_then(|v| v.as_u64()),
+            range_size: map_get(&opts, "range_size").and_then(|v| v.as_u64()),
+            run_count_per_level: map_get(&opts, "run_count_per_level").and_then(|v| v.as_u64()),
+            run_size_ratio: map_get(&opts, "run_size_ratio").and_then(|v| v.as_f64()),
+            func: match map_get(&opts, "func").and_then(|v| v.as_u64()) {
+                Some(id) => Some(LiveFunction::find(id as u32)?.ok_or(anyhow!("Can't find function {} of index '{}'", id, name))?.name),
+                None => None,
+            },
+            dimension: map_get(&opts, "dimension").and_then(|v| v.as_u64()),
+            distance: map_get(&opts, "distance").and_then(|v| v.as_str()).map(|distance| distance.to_lowercase()),
+        })
+    }
+}
+
+impl LiveCollation {
+    pub fn find(name: &str) -> Result<Option<LiveCollation>, anyhow::Error> {
+        let tuple = system_space("_collation")?.index("name")
+            .ok_or(anyhow!("Can't find index 'name' of system space '_collation'"))?
+            .get(&(name,))?;
+        let tuple = match tuple {
+            Some(tuple) => tuple.as_struct::<Vec<rmpv::Value>>()?,
+            None => return Ok(None),
+        };
+        // [id, name, owner, type, locale, opts]
+        let mut options = vec![];
+        if let Some(opts) = tuple.get(5).and_then(|v| v.as_map()) {
+            for (key, value) in opts {
+                if let Some(key) = key.as_str() {
+                    let value = match value.as_str() {
+                        Some(value) => value.to_string(),
+                        None => format!("{}", value),
+                    };
+                    options.push((key.to_string(), value));
+                }
+            }
+        }
+        Ok(Some(LiveCollation {
+            id: tuple.get(0).and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+            name: name.to_string(),
+            locale: tuple.get(4).and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            options,
+        }))
+    }
+}
+
+impl LiveFunction {
+    pub fn find(id: u32) -> Result<Option<LiveFunction>, anyhow::Error> {
+        match system_space("_func")?.get(&(id,))? {
+            Some(tuple) => Ok(Some(LiveFunction::from_tuple(tuple.as_struct::<Vec<rmpv::Value>>()?))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn find_by_name(name: &str) -> Result<Option<LiveFunction>, anyhow::Error> {
+        let tuple = system_space("_func")?.index("name")
+            .ok_or(anyhow!("Can't find index 'name' of system space '_func'"))?
+            .get(&(name,))?;
+        match tuple {
+            Some(tuple) => Ok(Some(LiveFunction::from_tuple(tuple.as_struct::<Vec<rmpv::Value>>()?))),
+            None => Ok(None),
+        }
+    }
+
+    fn from_tuple(tuple: Vec<rmpv::Value>) -> LiveFunction {
+        // [id, owner, name, setuid, language, body, routine_type, param_list, returns, aggregate,
+        //  sql_data_access, is_deterministic, is_sandboxed, is_null_call, exports, opts, ...]
+        LiveFunction {
+            id: tuple.get(0).and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+            name: tuple.get(2).and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            language: tuple.get(4).and_then(|v| v.as_str()).unwrap_or("LUA").to_lowercase(),
+            body: tuple.get(5).and_then(|v| v.as_str()).filter(|body| !body.is_empty()).map(|body| body.to_string()),
+            is_deterministic: tuple.get(11).and_then(|v| v.as_bool()).unwrap_or(false),
+            is_sandboxed: tuple.get(12).and_then(|v| v.as_bool()).unwrap_or(false),
+            is_multikey: tuple.get(15).map(|opts| map_bool(opts, "is_multikey")).unwrap_or(false),
+        }
+    }
+}
+
+fn value_i64(value: Option<&rmpv::Value>) -> i64 {
+    value.and_then(|v| v.as_i64()).unwrap_or(0)
+}
+
+impl LiveSequence {
+    pub fn find(id: u32) -> Result<Option<LiveSequence>, anyhow::Error> {
+        let tuple = match system_space("_sequence")?.get(&(id,))? {
+            Some(tuple) => tuple.as_struct::<Vec<rmpv::Value>>()?,
+            None => return Ok(None),
+        };
+        // [id, owner, name, step, min, max, start, cache, cycle]
+        Ok(Some(LiveSequence {
+            id,
+            name: tuple.get(2).and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            step: value_i64(tuple.get(3)),
+            min: value_i64(tuple.get(4)),
+            max: value_i64(tuple.get(5)),
+            start: value_i64(tuple.get(6)),
+            cycle: tuple.get(8).and_then(|v| v.as_bool()).unwrap_or(false),
+            field_index: None,
+            path: None,
+        }))
+    }
+
+    fn attached(space_id: u32) -> Result<Option<LiveSequence>, anyhow::Error> {
+        let tuple = match system_space("_space_sequence")?.get(&(space_id,))? {
+            Some(tuple) => tuple.as_struct::<Vec<rmpv::Value>>()?,
+            None => return Ok(None),
+        };
+        // [space_id, sequence_id, is_generated, field, path]
+        let sequence_id = tuple.get(1).and_then(|v| v.as_u64()).ok_or(anyhow!("Incorrect _space_sequence tuple of space {}", space_id))?;
+        let mut sequence = match LiveSequence::find(sequence_id as u32)? {
+            Some(sequence) => sequence,
+            None => return Ok(None),
+        };
+        sequence.field_index = Some(tuple.get(3).and_then(|v| v.as_u64()).unwrap_or(0) as u32 + 1);
+        sequence.path = tuple.get(4).and_then(|v| v.as_str()).filter(|path| !path.is_empty()).map(|path| path.to_string());
+        Ok(Some(sequence))
+    }
+}
+
+impl LiveSpace {
+    // user spaces, system ones have ids below 512
+    pub fn all() -> Result<Vec<LiveSpace>, anyhow::Error> {
+        let mut spaces = vec![];
+        for tuple in system_space("_space")?.select(IteratorType::GE, &(512,))? {
+            let space = LiveSpace::from_tuple(tuple.as_struct::<SpaceTuple>()?)?;
+            if !space.name.starts_with('_') {
+                spaces.push(space);
+            }
+        }
+        Ok(spaces)
+    }
+
+    pub fn find(name: &str) -> Result<Option<LiveSpace>, anyhow::Error> {
+        let tuple = system_space("_space")?.index("name")
+            .ok_or(anyhow!("Can't find index 'name' of system space '_space'"))?
+            .get(&(name,))?;
+        match tuple {
+            Some(tuple) => Ok(Some(LiveSpace::from_tuple(tuple.as_struct::<SpaceTuple>()?)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn from_tuple(tuple: SpaceTuple) -> Result<Self, anyhow::Error> {
+        let SpaceTuple(id, owner, name, engine, field_count, flags, format) = tuple;
+        let mut fields = vec![];
+        for field in &format {
+            fields.push(LiveField::from_value(field)?);
+        }
+        let mut indexes = vec![];
+        for tuple in system_space("_index")?.select(IteratorType::Eq, &(id,))? {
+            indexes.push(LiveIndex::from_tuple(tuple.as_struct::<IndexTuple>()?)?);
+        }
+        Ok(LiveSpace {
+            id,
+            name,
+            owner: user_name(owner)?,
+            engine,
+            field_count,
+            // replica-local spaces are stored with group_id = 1
+            is_local: map_get(&flags, "group_id").and_then(|v| v.as_u64()) == Some(1),
+            temporary: map_bool(&flags, "temporary"),
+            is_sync: map_bool(&flags, "is_sync"),
+            defer_deletes: map_bool(&flags, "defer_deletes"),
+            format: fields,
+            indexes,
+            sequence: LiveSequence::attached(id)?,
+        })
+    }
+
+    pub fn field_index(&self, name: &str) -> Option<u32> {
+        self.format.iter().position(|field| field.name == name).map(|index| index as u32 + 1)
+    }
+    pub fn index(&self, name: &str) -> Option<&LiveIndex> {
+        self.indexes.iter().find(|index| index.name == name)
+    }
+}
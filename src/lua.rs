@@ -0,0 +1,105 @@
+use anyhow::anyhow;
+
+// DDL that the tarantool crate doesn't expose (space:format(), index:alter(), ...)
+// is executed as Lua in the tx thread.
+
+pub(crate) fn exec(code: &str) -> Result<(), anyhow::Error> {
+    tarantool::lua_state().exec(code).map_err(|err| anyhow!("Lua error: {}\n{}", err, code))
+}
+
+pub(crate) fn string(value: &str) -> String {
+    let mut str = format!("\"");
+    for c in value.chars() {
+        match c {
+            '"' => str += "\\\"",
+            '\\' => str += "\\\\",
+            '\n' => str += "\\n",
+            '\r' => str += "\\r",
+            '\0' => str += "\\0",
+            c => str.push(c),
+        }
+    }
+    str += "\"";
+    str
+}
+
+pub(crate) fn space(name: &str) -> String {
+    format!("box.space[{}]", string(name))
+}
+pub(crate) fn index(space_name: &str, index_name: &str) -> String {
+    format!("{}.index[{}]", space(space_name), string(index_name))
+}
+
+pub(crate) fn format(fields: &[crate::Field]) -> String {
+    let mut entries = vec![];
+    for field in fields {
+        let mut entry = format!("{{name = {}, type = {}, is_nullable = {}", string(&field.name), string(&crate::diff::type_name(&field.field_type)), field.is_nullable.unwrap_or(false));
+        if let Some(collation) = &field.collation {
+            entry += &format!(", collation = {}", string(collation));
+        }
+        entry += "}";
+        entries.push(entry);
+    }
+    format!("{{{}}}", entries.join(", "))
+}
+
+pub(crate) fn index_options(index: &crate::Index) -> String {
+    let mut parts = vec![];
+    for part in &index.parts {
+        let part = &part.part;
+        let mut str = format!("{{field = {}, type = {}, is_nullable = {}", part.field_index, string(&crate::diff::type_name(&part.field_type)), part.is_nullable.unwrap_or(false));
+        if let Some(collation) = &part.collation {
+            str += &format!(", collation = {}", string(collation));
+        }
+        if let Some(path) = &part.path {
+            str += &format!(", path = {}", string(path));
+        }
+        str += "}";
+        parts.push(str);
+    }
+    let mut options = format!("type = {}, unique = {}, parts = {{{}}}", string(&crate::diff::type_name(&index.index_type)), index.unique, parts.join(", "));
+    for (name, value) in [("bloom_fpr", index.bloom_fpr.map(|v| v.to_string())), ("page_size", index.page_size.map(|v| v.to_string())),
+                          ("range_size", index.range_size.map(|v| v.to_string())), ("run_count_per_level", index.run_count_per_level.map(|v| v.to_string())),
+                          ("run_size_ratio", index.run_size_ratio.map(|v| v.to_string()))] {
+        if let Some(value) = value {
+            options += &format!(", {} = {}", name, value);
+        }
+    }
+    if let Some(func) = &index.func {
+        options += &format!(", func = {}", string(func));
+    }
+    if let Some(dimension) = index.dimension {
+        options += &format!(", dimension = {}", dimension);
+    }
+    if let Some(distance) = &index.distance {
+        options += &format!(", distance = {}", string(&crate::diff::type_name(distance)));
+    }
+    format!("{{{}}}", options)
+}
+
+pub(crate) fn function_options(function: &crate::Function) -> String {
+    let mut options = format!("language = {}, is_deterministic = {}, if_not_exists = true", string(&function.language.to_uppercase()), function.is_deterministic);
+    if let Some(body) = &function.body {
+        options += &format!(", body = {}, is_sandboxed = {}", string(body), function.is_sandboxed);
+    }
+    if function.is_multikey {
+        options += ", opts = {is_multikey = true}";
+    }
+    format!("{{{}}}", options)
+}
+
+pub(crate) fn sequence_options(sequence: &crate::Sequence) -> String {
+    let mut options = vec![];
+    for (name, value) in [("start", sequence.start), ("step", sequence.step), ("min", sequence.min), ("max", sequence.max)] {
+        if let Some(value) = value {
+            options.push(format!("{} = {}LL", name, value));
+        }
+    }
+    options.push(format!("cycle = {}", sequence.cycle));
+    format!("{{{}}}", options.join(", "))
+}
+
+pub(crate) fn collation_options(options: &[(String, String)]) -> String {
+    let options: Vec<String> = options.iter().map(|(name, value)| format!("{} = {}", name, string(value))).collect();
+    format!("{{{}}}", options.join(", "))
+}
@@ -0,0 +1,272 @@
+use anyhow::anyhow;
+use serde::{Serialize, Deserialize};
+use serde::de::IntoDeserializer;
+use tarantool::index::{IndexFieldType, IndexType, IteratorType};
+use tarantool::space::SpaceEngineType;
+
+use crate::{Field, FieldType, Index, IndexPart, Space};
+
+pub const MIGRATIONS_SPACE_NAME: &str = "_tarantool_schema_migrations";
+
+// Rewrites a single tuple of the migrated space. `None` leaves the tuple as is.
+pub type TransformFn = fn(&tarantool::tuple::Tuple) -> Result<Option<tarantool::tuple::Tuple>, anyhow::Error>;
+
+#[derive(Debug, Clone)]
+pub struct Transform {
+    pub space: String,
+    pub function: TransformFn,
+    pub batch_size: usize,
+}
+
+// Inverse of a migration step, applied by `rollback_to`
+#[derive(Debug, Clone)]
+pub struct Down {
+    pub transform: Option<Transform>,
+    // (space, index)
+    pub drop_indexes: Vec<(String, String)>,
+    // (space, format before the step)
+    pub restore_format: Vec<(String, Vec<Field>)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: u64,
+    pub description: String,
+    pub checksum: String,
+    pub transform: Option<Transform>,
+    pub down: Option<Down>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub version: u64,
+    pub checksum: String,
+    pub applied_at: u64,
+    pub description: String,
+}
+impl tarantool::tuple::AsTuple for AppliedMigration {}
+
+// Spaces of the crate itself: a plain format with the unique key on the first field
+pub(crate) fn internal_space(name: &str, format: Vec<(&str, FieldType)>) -> Space {
+    let key = format[0].0.to_string();
+    Space {
+        name: name.to_string(),
+        engine: SpaceEngineType::Memtx,
+        is_local: false,
+        temporary: false,
+        is_sync: false,
+        id: None,
+        field_count: 0,
+        user: None,
+        defer_deletes: false,
+        format: format.into_iter().map(|(name, field_type)| Field { name: name.to_string(), is_nullable: Some(false), field_type, collation: None }).collect(),
+        indexes: vec![
+            Index {
+                name: "primary".to_string(),
+                index_type: IndexType::Tree,
+                unique: true,
+                parts: vec![IndexPart {
+                    path: key,
+                    index_field_type: IndexFieldType::Unsigned,
+                    is_nullable: Some(false),
+                    part: tarantool::index::IndexPart {
+                        field_index: 1,
+                        field_type: IndexFieldType::Unsigned,
+                        collation: None,
+                        is_nullable: Some(false),
+                        path: None,
+                    },
+                }],
+                bloom_fpr: None,
+                page_size: None,
+                range_size: None,
+                run_count_per_level: None,
+                run_size_ratio: None,
+                func: None,
+                dimension: None,
+                distance: None,
+            },
+        ],
+        sequence: None,
+    }
+}
+
+fn migrations_space() -> Space {
+    internal_space(MIGRATIONS_SPACE_NAME, vec![
+        ("version", FieldType::Unsigned),
+        ("checksum", FieldType::String),
+        ("applied_at", FieldType::Unsigned),
+        ("description", FieldType::String),
+    ])
+}
+
+pub fn history() -> Result<Vec<AppliedMigration>, anyhow::Error> {
+    let space = match tarantool::space::Space::find(MIGRATIONS_SPACE_NAME) {
+        Some(space) => space,
+        None => return Ok(vec![]),
+    };
+    let mut applied = vec![];
+    for tuple in space.select(IteratorType::All, &())? {
+        applied.push(tuple.as_struct::<AppliedMigration>()?);
+    }
+    Ok(applied)
+}
+
+pub fn current_version() -> Result<Option<u64>, anyhow::Error> {
+    Ok(history()?.iter().map(|migration| migration.version).max())
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0)
+}
+
+// Brings the declared spaces to their current state and records every migration
+// newer than the last applied one. Returns names of the spaces created from scratch.
+pub fn migrate(spaces: &[Space], migrations: &[Migration]) -> Result<Vec<String>, anyhow::Error> {
+    for pair in migrations.windows(2) {
+        if pair[0].version >= pair[1].version {
+            return Err(anyhow!("Migration versions must be increasing: {} goes before {}", pair[0].version, pair[1].version));
+        }
+    }
+
+    migrations_space().alter()?;
+
+    let history = history()?;
+    for applied in &history {
+        match migrations.iter().find(|migration| migration.version == applied.version) {
+            Some(migration) => {
+                if migration.checksum != applied.checksum {
+                    return Err(anyhow!("Migration {} was changed after it had been applied: checksum is '{}' but was '{}'", applied.version, migration.checksum, applied.checksum));
+                }
+            },
+            None => return Err(anyhow!("Migration {} ('{}') is applied but isn't declared in the schema", applied.version, applied.description)),
+        }
+    }
+    let current = history.iter().map(|migration| migration.version).max();
+    if let Some(current) = current {
+        for migration in migrations {
+            if migration.version <= current && !history.iter().any(|applied| applied.version == migration.version) {
+                return Err(anyhow!("Migration {} ('{}') isn't applied but the database is already at version {}", migration.version, migration.description, current));
+            }
+        }
+    }
+    let pending: Vec<&Migration> = migrations.iter()
+        .filter(|migration| current.map(|current| migration.version > current).unwrap_or(true))
+        .collect();
+
+    // spaces rewritten by pending transforms get a relaxed format first,
+    // the declared one is set after the data has been converted
+    let transformed = |space: &Space| pending.iter().any(|migration| migration.transform.as_ref().map(|transform| transform.space == space.name).unwrap_or(false));
+    let mut created = vec![];
+    for space in spaces {
+        if !transformed(space) {
+            continue;
+        }
+        let changes = space.diff()?;
+        if changes.iter().any(|change| matches!(change, crate::diff::Change::SpaceAdded)) {
+            space.create()?;
+            created.push(space.name.clone());
+        } else {
+            space.relax(&changes)?;
+        }
+    }
+
+    let mut migrations_space = migrations_space().space()?;
+    for migration in &pending {
+        log::info!("applying schema migration {} '{}'", migration.version, migration.description);
+        if let Some(transform) = &migration.transform {
+            let count = crate::backfill::run(migration.version, transform)?;
+            log::info!("migration {}: {} tuples of space '{}' processed", migration.version, count, transform.space);
+        }
+        crate::transaction(|| {
+            migrations_space.insert(&AppliedMigration {
+                version: migration.version,
+                checksum: migration.checksum.clone(),
+                applied_at: now(),
+                description: migration.description.clone(),
+            })?;
+            crate::backfill::finish(migration.version)
+        })?;
+    }
+
+    for space in spaces {
+        if transformed(space) && !created.contains(&space.name) {
+            space.set_format()?;
+        }
+        let changes = space.alter()?;
+        if changes.iter().any(|change| matches!(change, crate::diff::Change::SpaceAdded)) {
+            created.push(space.name.clone());
+        }
+    }
+
+    Ok(created)
+}
+
+// Live format of the space where fields outside of its indexes accept anything
+fn relaxed_format(space: &str) -> Result<Vec<Field>, anyhow::Error> {
+    let live = crate::live::LiveSpace::find(space)?.ok_or(anyhow!("Can't find space '{}'", space))?;
+    let mut format = vec![];
+    for (position, field) in live.format.iter().enumerate() {
+        let indexed = live.indexes.iter().any(|index| index.parts.iter().any(|part| part.field_index as usize == position + 1));
+        if !indexed {
+            format.push(Field { name: field.name.clone(), is_nullable: Some(true), field_type: FieldType::Any, collation: None });
+            continue;
+        }
+        let field_type = FieldType::deserialize(field.field_type.as_str().into_deserializer())
+            .map_err(|err: serde::de::value::Error| anyhow!("Field '{}' of space '{}' has unknown type: {}", field.name, space, err))?;
+        format.push(Field { name: field.name.clone(), is_nullable: Some(field.is_nullable), field_type, collation: field.collation.clone() });
+    }
+    Ok(format)
+}
+
+// Reverts applied migrations newer than `version`, the newest first
+pub fn rollback_to(migrations: &[Migration], version: u64) -> Result<(), anyhow::Error> {
+    let mut history = history()?;
+    history.sort_by(|a, b| b.version.cmp(&a.version));
+    let history: Vec<AppliedMigration> = history.into_iter().filter(|applied| applied.version > version).collect();
+
+    // check everything before touching the data
+    for applied in &history {
+        match migrations.iter().find(|migration| migration.version == applied.version) {
+            Some(migration) if migration.down.is_some() => {},
+            Some(_) => return Err(anyhow!("Can't roll back to {}: migration {} ('{}') has no down", version, applied.version, applied.description)),
+            None => return Err(anyhow!("Can't roll back to {}: migration {} ('{}') isn't declared in the schema", version, applied.version, applied.description)),
+        }
+    }
+
+    let mut migrations_space = migrations_space().space()?;
+    for applied in &history {
+        let migration = migrations.iter().find(|migration| migration.version == applied.version).unwrap();
+        let down = migration.down.as_ref().unwrap();
+        log::info!("rolling back schema migration {} '{}'", migration.version, migration.description);
+
+        for (space, index) in &down.drop_indexes {
+            if crate::live::LiveSpace::find(space)?.map(|live| live.index(index).is_some()).unwrap_or(false) {
+                log::info!("space '{}': dropping index '{}'", space, index);
+                crate::lua::exec(&format!("{}:drop()", crate::lua::index(space, index)))
+                    .map_err(|err| anyhow!("Can't drop index '{}' of space '{}': {}", index, space, err))?;
+            }
+        }
+
+        if let Some(transform) = &down.transform {
+            // the inverse transform may write values of the previous types
+            let relaxed = relaxed_format(&transform.space)?;
+            crate::lua::exec(&format!("{}:format({})", crate::lua::space(&transform.space), crate::lua::format(&relaxed)))
+                .map_err(|err| anyhow!("Can't update format of space '{}': {}", transform.space, err))?;
+            let count = crate::backfill::run(migration.version, transform)?;
+            log::info!("migration {}: {} tuples of space '{}' processed", migration.version, count, transform.space);
+        }
+
+        for (space, format) in &down.restore_format {
+            log::info!("space '{}': restoring format", space);
+            crate::lua::exec(&format!("{}:format({})", crate::lua::space(space), crate::lua::format(format)))
+                .map_err(|err| anyhow!("Can't restore format of space '{}': {}", space, err))?;
+        }
+
+        crate::transaction(|| {
+            migrations_space.delete(&(migration.version,))?;
+            crate::backfill::finish(migration.version)
+        })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use anyhow::anyhow;
+
+// Runs `f` in a box transaction: commits on Ok, rolls back on Err.
+// Transactions don't nest, calling it inside another one is an error.
+pub fn transaction<T, F: FnOnce() -> Result<T, anyhow::Error>>(f: F) -> Result<T, anyhow::Error> {
+    if unsafe { tarantool::ffi::tarantool::box_txn() } {
+        return Err(anyhow!("Can't begin transaction: another transaction is already active"));
+    }
+    if unsafe { tarantool::ffi::tarantool::box_txn_begin() } < 0 {
+        return Err(anyhow!("Can't begin transaction: {}", tarantool::error::TarantoolError::last()));
+    }
+    match f() {
+        Ok(value) => {
+            if unsafe { tarantool::ffi::tarantool::box_txn_commit() } < 0 {
+                let err = anyhow!("Can't commit transaction: {}", tarantool::error::TarantoolError::last());
+                unsafe { tarantool::ffi::tarantool::box_txn_rollback() };
+                return Err(err);
+            }
+            Ok(value)
+        },
+        Err(err) => {
+            unsafe { tarantool::ffi::tarantool::box_txn_rollback() };
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+
+// Rust representations of field types that have no std counterpart
+
+const MP_DATETIME: i8 = 4;
+const MP_INTERVAL: i8 = 6;
+
+// `datetime`: seconds since the epoch plus optional nanoseconds and timezone
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Datetime {
+    pub seconds: i64,
+    pub nsec: i32,
+    // minutes east of UTC
+    pub tzoffset: i16,
+    pub tzindex: i16,
+}
+impl Datetime {
+    pub fn from_seconds(seconds: i64) -> Self {
+        Datetime { seconds, ..Default::default() }
+    }
+}
+
+impl Serialize for Datetime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut data = self.seconds.to_le_bytes().to_vec();
+        // the tail is omitted when all of it is zero
+        if self.nsec != 0 || self.tzoffset != 0 || self.tzindex != 0 {
+            data.extend_from_slice(&self.nsec.to_le_bytes());
+            data.extend_from_slice(&self.tzoffset.to_le_bytes());
+            data.extend_from_slice(&self.tzindex.to_le_bytes());
+        }
+        rmpv::Value::Ext(MP_DATETIME, data).serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Datetime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = match rmpv::Value::deserialize(deserializer)? {
+            rmpv::Value::Ext(MP_DATETIME, data) if data.len() == 8 || data.len() == 16 => data,
+            value => return Err(D::Error::custom(format!("expected datetime extension, got {}", value))),
+        };
+        let mut datetime = Datetime::from_seconds(i64::from_le_bytes(data[0..8].try_into().unwrap()));
+        if data.len() == 16 {
+            datetime.nsec = i32::from_le_bytes(data[8..12].try_into().unwrap());
+            datetime.tzoffset = i16::from_le_bytes(data[12..14].try_into().unwrap());
+            datetime.tzindex = i16::from_le_bytes(data[14..16].try_into().unwrap());
+        }
+        Ok(datetime)
+    }
+}
+
+// `interval`: calendar units are kept apart, a month isn't a fixed number of days
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub year: i64,
+    pub month: i64,
+    pub week: i64,
+    pub day: i64,
+    pub hour: i64,
+    pub min: i64,
+    pub sec: i64,
+    pub nsec: i64,
+    // 0 - excess, 1 - none, 2 - last
+    pub adjust: i64,
+}
+impl Default for Interval {
+    fn default() -> Self {
+        Interval { year: 0, month: 0, week: 0, day: 0, hour: 0, min: 0, sec: 0, nsec: 0, adjust: 1 }
+    }
+}
+impl Interval {
+    fn units(&self) -> [(u8, i64); 9] {
+        [(0, self.year), (1, self.month), (2, self.week), (3, self.day), (4, self.hour),
+         (5, self.min), (6, self.sec), (7, self.nsec), (8, self.adjust)]
+    }
+}
+
+impl Serialize for Interval {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+        // a raw count byte, then MessagePack (unit, value) pairs for the non-default units
+        let defaults = Interval::default().units();
+        let units: Vec<(u8, i64)> = self.units().iter().zip(defaults.iter()).filter(|(unit, default)| unit != default).map(|(unit, _)| *unit).collect();
+        let mut data = vec![units.len() as u8];
+        for (unit, value) in units {
+            rmpv::encode::write_value(&mut data, &rmpv::Value::from(unit)).map_err(S::Error::custom)?;
+            rmpv::encode::write_value(&mut data, &rmpv::Value::from(value)).map_err(S::Error::custom)?;
+        }
+        rmpv::Value::Ext(MP_INTERVAL, data).serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Interval {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = match rmpv::Value::deserialize(deserializer)? {
+            rmpv::Value::Ext(MP_INTERVAL, data) if !data.is_empty() => data,
+            value => return Err(D::Error::custom(format!("expected interval extension, got {}", value))),
+        };
+        let mut interval = Interval::default();
+        let mut reader = &data[1..];
+        for _ in 0..data[0] {
+            let unit = rmpv::decode::read_value(&mut reader).map_err(D::Error::custom)?;
+            let value = rmpv::decode::read_value(&mut reader).map_err(D::Error::custom)?;
+            let value = value.as_i64().ok_or(D::Error::custom(format!("interval unit {} isn't an integer", unit)))?;
+            match unit.as_u64() {
+                Some(0) => interval.year = value,
+                Some(1) => interval.month = value,
+                Some(2) => interval.week = value,
+                Some(3) => interval.day = value,
+                Some(4) => interval.hour = value,
+                Some(5) => interval.min = value,
+                Some(6) => interval.sec = value,
+                Some(7) => interval.nsec = value,
+                Some(8) => interval.adjust = value,
+                _ => return Err(D::Error::custom(format!("unknown interval unit {}", unit))),
+            }
+        }
+        Ok(interval)
+    }
+}
+
+// `number`: any integer or floating point value
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Number {
+    Integer(i64),
+    // above i64::MAX
+    Unsigned(u64),
+    Double(f64),
+}
+impl From<i64> for Number {
+    fn from(value: i64) -> Self { Number::Integer(value) }
+}
+impl From<u64> for Number {
+    fn from(value: u64) -> Self {
+        if value <= i64::MAX as u64 { Number::Integer(value as i64) } else { Number::Unsigned(value) }
+    }
+}
+impl From<f64> for Number {
+    fn from(value: f64) -> Self { Number::Double(value) }
+}
+impl std::fmt::Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::Integer(value) => write!(f, "{}", value),
+            Number::Unsigned(value) => write!(f, "{}", value),
+            Number::Double(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> (rmpv::Value, T) {
+        let encoded = rmpv::ext::to_value(value).unwrap();
+        let decoded = rmpv::ext::from_value(encoded.clone()).unwrap();
+        (encoded, decoded)
+    }
+
+    #[test]
+    fn datetime_without_tail_is_8_bytes() {
+        let datetime = Datetime::from_seconds(1_700_000_000);
+        let (encoded, decoded) = round_trip(&datetime);
+        assert_eq!(encoded, rmpv::Value::Ext(MP_DATETIME, 1_700_000_000i64.to_le_bytes().to_vec()));
+        assert_eq!(decoded, datetime);
+    }
+
+    #[test]
+    fn datetime_with_tail_is_16_bytes() {
+        let datetime = Datetime { seconds: -1, nsec: 500, tzoffset: 180, tzindex: 0 };
+        let (encoded, decoded) = round_trip(&datetime);
+        match &encoded {
+            rmpv::Value::Ext(MP_DATETIME, data) => assert_eq!(data.len(), 16),
+            value => panic!("unexpected {}", value),
+        }
+        assert_eq!(decoded, datetime);
+    }
+
+    #[test]
+    fn datetime_rejects_other_extensions() {
+        assert!(rmpv::ext::from_value::<Datetime>(rmpv::Value::Ext(MP_INTERVAL, vec![0; 8])).is_err());
+        assert!(rmpv::ext::from_value::<Datetime>(rmpv::Value::Ext(MP_DATETIME, vec![0; 12])).is_err());
+    }
+
+    #[test]
+    fn interval_keeps_only_non_default_units() {
+        let interval = Interval { year: 1, day: -2, ..Default::default() };
+        let (encoded, decoded) = round_trip(&interval);
+        // 2 pairs: year = 1, day = -2
+        assert_eq!(encoded, rmpv::Value::Ext(MP_INTERVAL, vec![2, 0x00, 0x01, 0x03, 0xfe]));
+        assert_eq!(decoded, interval);
+    }
+
+    #[test]
+    fn interval_round_trips_every_unit() {
+        let interval = Interval { year: -1, month: 2, week: 3, day: 4, hour: 5, min: 6, sec: 7, nsec: 800_000_000, adjust: 0 };
+        assert_eq!(round_trip(&interval).1, interval);
+        assert_eq!(round_trip(&Interval::default()).0, rmpv::Value::Ext(MP_INTERVAL, vec![0]));
+    }
+
+    #[test]
+    fn interval_rejects_unknown_units() {
+        assert!(rmpv::ext::from_value::<Interval>(rmpv::Value::Ext(MP_INTERVAL, vec![1, 0x09, 0x01])).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+use crate::diff::Change;
+
+#[derive(Debug, Clone)]
+pub struct VerifyError {
+    pub space: String,
+    pub changes: Vec<Change>,
+}
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Space '{}' doesn't match the declared schema:", self.space)?;
+        for change in &self.changes {
+            write!(f, "\n  - {}", change)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for VerifyError {}